@@ -1,64 +1,342 @@
 use clap::{App, Arg};
 use std::{
+    borrow::Cow,
     error::Error,
-    io::{self, BufReader, BufRead},
+    io::{self, BufReader, BufRead, Write},
+    ops::Range,
     str
 };
 use std::fs::File;
-use regex::Regex;
+use regex::{bytes, Regex};
 use Extract::*;
-use csv::{StringRecord, WriterBuilder, ReaderBuilder};
+use csv::{QuoteStyle, Terminator, WriterBuilder, ReaderBuilder};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 type RetType<T> = Result<T, Box<dyn Error>>;
-type PositionList = Vec<usize>;
+type PositionList = Vec<Span>;
+
+/// One element of a LIST such as `3`, `2-5`, `-4` or `7-`, as zero-based
+/// positions from `start` up to but not including `end`. Without an `end`
+/// it runs to the end of each line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    start: usize,
+    end: Option<usize>,
+}
+
+/// A column name to match in the header, as given and as a pattern.
+#[derive(Debug)]
+pub struct Name {
+    given: String,
+    pattern: Regex,
+}
 
 #[derive(Debug)]
 pub enum Extract {
     Fields(PositionList),
+    Names(Vec<Name>),
     Bytes(PositionList),
     Chars(PositionList),
 }
 
+/// What --chars counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharUnit {
+    /// Unicode scalar values.
+    Scalar,
+    /// Extended grapheme clusters, so that accents and emoji stay whole.
+    Grapheme,
+    /// Terminal columns, with wide East Asian characters taking two.
+    Width,
+}
+
+/// What separates fields outside of --csv.
+#[derive(Debug)]
+pub enum Delimiter {
+    Literal(String),
+    Pattern(bytes::Regex),
+    /// Fixed-width columns at these character positions.
+    Columns(PositionList),
+}
+
+impl Delimiter {
+    fn is_in(&self, line: &[u8]) -> bool {
+        match self {
+            Delimiter::Literal(delim) => line.windows(delim.len()).any(|w| w == delim.as_bytes()),
+            Delimiter::Pattern(re) => re.is_match(line),
+            Delimiter::Columns(_) => true,
+        }
+    }
+
+    // Lines are split as bytes, so that text that isn't UTF-8 comes through
+    // as it is, as with cut.
+    fn split<'a>(&self, line: &'a [u8]) -> Vec<&'a [u8]> {
+        match self {
+            Delimiter::Literal(delim) => {
+                let delim = delim.as_bytes();
+                let mut fields = vec![];
+                let (mut start, mut i) = (0, 0);
+                while i + delim.len() <= line.len() {
+                    if line[i..].starts_with(delim) {
+                        fields.push(&line[start..i]);
+                        i += delim.len();
+                        start = i;
+                    } else {
+                        i += 1;
+                    }
+                }
+                fields.push(&line[start..]);
+                fields
+            }
+            Delimiter::Pattern(re) => {
+                // As in awk, delimiters at either end of the line don't
+                // make empty fields there.
+                let start = re.find(line).filter(|m| m.start() == 0).map_or(0, |m| m.end());
+                let end = re
+                    .find_iter(&line[start..])
+                    .last()
+                    .filter(|m| start + m.end() == line.len())
+                    .map_or(line.len(), |m| start + m.start());
+                re.split(&line[start..end]).collect()
+            }
+            Delimiter::Columns(columns) => {
+                // Where each UTF-8 character starts, and the end of the line,
+                // so that columns past the end come out empty.
+                let starts: Vec<usize> = (0..line.len())
+                    .filter(|&i| line[i] & 0xC0 != 0x80)
+                    .chain([line.len()])
+                    .collect();
+                let at = |pos: usize| starts[pos.min(starts.len() - 1)];
+                columns
+                    .iter()
+                    .map(|column| &line[at(column.start)..column.end.map_or(line.len(), at)])
+                    .collect()
+            }
+        }
+    }
+
+    // What joins the output unless --output-delimiter says otherwise: the
+    // delimiter itself, a space as in awk, or a tab for columns.
+    fn joiner(&self) -> &str {
+        match self {
+            Delimiter::Literal(delim) => delim,
+            Delimiter::Pattern(_) => " ",
+            Delimiter::Columns(_) => "\t",
+        }
+    }
+}
+
+/// How --csv reads and writes quoted fields.
+#[derive(Debug)]
+pub struct CsvStyle {
+    delimiter: u8,
+    quote_style: QuoteStyle,
+    quote: u8,
+    // Without one, quotes in quoted fields are doubled.
+    escape: Option<u8>,
+}
+
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
-    delimiter: u8,
+    delimiter: Delimiter,
+    output_delimiter: Option<String>,
+    complement: bool,
+    reorder: bool,
+    header: bool,
+    csv: Option<CsvStyle>,
+    only_delimited: bool,
+    trim: bool,
+    no_split: bool,
+    char_unit: CharUnit,
+    terminator: u8,
     extract: Extract,
 }
 
-fn parse_pos(range: &str) -> RetType<PositionList> {
-    let mut fields: Vec<usize> = vec![];
-    let range_re = Regex::new(r"(\d+)?-(\d+)?").unwrap();
-    for val in range.split(',') {
-        if let Some(cap) = range_re.captures(val) {
-            let n1: &usize = &cap[1].parse()?;
-            let n2: &usize = &cap[2].parse()?;
+// With `reorder`, spans are kept in the order given, repeats included,
+// rather than merged.
+fn parse_pos(range: &str, reorder: bool) -> RetType<PositionList> {
+    let range_re = Regex::new(r"^([0-9]+)?-([0-9]+)?$").unwrap();
+    let num_re = Regex::new(r"^[0-9]+$").unwrap();
+    let illegal = |val: &str| -> Box<dyn Error> {
+        From::from(format!("illegal list value: \"{}\"", val))
+    };
+    let position = |val: &str, num: &str| -> RetType<usize> {
+        match num.parse() {
+            Ok(0) => Err(From::from(format!(
+                "illegal list value: \"{}\": positions are numbered from 1",
+                val
+            ))),
+            Ok(n) => Ok(n),
+            Err(_) => Err(illegal(val)),
+        }
+    };
 
-            if n1 < n2 {
-                for n in *n1..=*n2 {
-                    fields.push(n);
+    let mut spans = vec![];
+    for val in range.split(',') {
+        if num_re.is_match(val) {
+            let n = position(val, val)?;
+            spans.push(Span { start: n - 1, end: Some(n) });
+        } else if let Some(cap) = range_re.captures(val) {
+            let n1 = cap.get(1).map(|m| position(val, m.as_str())).transpose()?;
+            let n2 = cap.get(2).map(|m| position(val, m.as_str())).transpose()?;
+            match (n1, n2) {
+                (None, None) => return Err(illegal(val)),
+                (Some(n1), Some(n2)) if n1 > n2 => {
+                    return Err(From::from(format!(
+                        "First number in range ({}) \
+                        must not be greater than the second number ({})",
+                        n1, n2
+                    )))
                 }
-            } else {
-                return Err(From::from(format!(
-                    "First number in range ({}) \
-                    must be lower than the second number({})",
-                    n1, n2
-                )));
+                _ => spans.push(Span { start: n1.map_or(0, |n| n - 1), end: n2 }),
             }
         } else {
-            match val.parse() {
-                Ok(n) if n > 0 => fields.push(n),
-                _ => {
-                    return Err(From::from(format!(
-                        "illegal list value: \"{}\"",
-                        val
-                    )))
+            return Err(illegal(val));
+        }
+    }
+
+    Ok(if reorder { spans } else { merge(spans) })
+}
+
+// Sorts spans and joins those that overlap or touch, so that each position
+// is selected once and in the order of the line, as cut does.
+fn merge(mut spans: PositionList) -> PositionList {
+    spans.sort_by_key(|span| span.start);
+    let mut merged: PositionList = vec![];
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if last.end.is_none_or(|end| span.start <= end) => {
+                last.end = match (last.end, span.end) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+            }
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+// The runs of positions `list` selects from a line with `len` of them, or
+// with `complement` those it leaves out, which needs `list` merged.
+fn ranges(list: &[Span], len: usize, complement: bool) -> Vec<Range<usize>> {
+    let selected = list
+        .iter()
+        .map(|span| span.start.min(len)..span.end.map_or(len, |end| end.min(len)))
+        .filter(|range| !range.is_empty());
+    if !complement {
+        return selected.collect();
+    }
+
+    let mut gaps = vec![];
+    let mut next = 0;
+    for range in selected {
+        if next < range.start {
+            gaps.push(next..range.start);
+        }
+        next = range.end;
+    }
+    if next < len {
+        gaps.push(next..len);
+    }
+    gaps
+}
+
+// A column name to match in the header: a regex between slashes such as
+// `/^year|date$/`, otherwise a glob where `*` and `?` match any run of
+// characters or any one.
+fn parse_name(name: &str) -> RetType<Name> {
+    let re = match name.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+        Some(re) => re.to_string(),
+        None => {
+            let mut re = String::from("^");
+            for c in name.chars() {
+                match c {
+                    '*' => re.push_str(".*"),
+                    '?' => re.push('.'),
+                    _ => re.push_str(&regex::escape(&c.to_string())),
                 }
             }
+            re.push('$');
+            re
         }
     };
+    let pattern = Regex::new(&re)
+        .map_err(|_| format!("illegal column name: \"{}\"", name))?;
+    Ok(Name { given: name.to_string(), pattern })
+}
+
+fn parse_names(names: &str) -> RetType<Vec<Name>> {
+    names.split(',').map(parse_name).collect()
+}
+
+// The columns of `headers` that `names` select, in order of the names unless
+// they are to be merged.
+fn name_spans(names: &[Name], headers: &[&str], reorder: bool) -> RetType<PositionList> {
+    let mut spans = vec![];
+    for name in names {
+        let before = spans.len();
+        spans.extend(
+            headers
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| name.pattern.is_match(column))
+                .map(|(i, _)| Span { start: i, end: Some(i + 1) }),
+        );
+        if spans.len() == before {
+            return Err(From::from(format!("no column matches \"{}\"", name.given)));
+        }
+    }
+    Ok(if reorder { spans } else { merge(spans) })
+}
 
-    Ok(fields.into_iter().map(|i| i - 1).collect())
+// Fixed-width columns given by their widths, such as `5,10,8`.
+fn parse_widths(widths: &str) -> RetType<PositionList> {
+    let mut columns = vec![];
+    let mut start = 0;
+    for val in widths.split(',') {
+        match val.parse::<usize>() {
+            Ok(width) if width > 0 && val.bytes().all(|b| b.is_ascii_digit()) => {
+                columns.push(Span { start, end: Some(start + width) });
+                start += width;
+            }
+            _ => return Err(From::from(format!("illegal width: \"{}\"", val))),
+        }
+    }
+    Ok(columns)
+}
+
+// Fixed-width columns given as `START:END` character positions, numbered
+// from 1 and inclusive, where either end may be left open.
+fn parse_columns(list: &str) -> RetType<PositionList> {
+    let column_re = Regex::new(r"^([0-9]+)?:([0-9]+)?$").unwrap();
+    let mut columns = vec![];
+    for val in list.split(',') {
+        let illegal = || -> Box<dyn Error> {
+            From::from(format!("illegal column: \"{}\"", val))
+        };
+        let cap = column_re.captures(val).ok_or_else(illegal)?;
+        let position = |i| -> RetType<Option<usize>> {
+            match cap.get(i).map(|m| m.as_str().parse::<usize>()) {
+                Some(Ok(0)) | Some(Err(_)) => Err(illegal()),
+                Some(Ok(n)) => Ok(Some(n)),
+                None => Ok(None),
+            }
+        };
+        match (position(1)?, position(2)?) {
+            (Some(start), Some(end)) if start > end => {
+                return Err(From::from(format!(
+                    "First number in range ({}) \
+                    must not be greater than the second number ({})",
+                    start, end
+                )))
+            }
+            (start, end) => columns.push(Span { start: start.map_or(0, |n| n - 1), end }),
+        }
+    }
+    Ok(columns)
 }
 
 pub fn get_args() -> RetType<Config> {
@@ -81,13 +359,129 @@ pub fn get_args() -> RetType<Config> {
                 .value_name("DELIM")
                 .default_value("\t")
         )
+        .arg(
+            Arg::new("regex_delim")
+                .help("split fields where RE matches, but not at either end of a line, such as '\\s+' for runs of whitespace as in awk, and join them with a space")
+                .long("regex-delim")
+                .alias("delimiter-regex")
+                .value_name("RE")
+                .conflicts_with_all(&["csv", "chars", "bytes"])
+        )
+        .arg(
+            Arg::new("widths")
+                .help("split lines into fixed-width columns of these many characters, to select with --fields")
+                .long("widths")
+                .value_name("WIDTHS")
+                .conflicts_with_all(&["regex_delim", "chars", "bytes"])
+        )
+        .arg(
+            Arg::new("columns")
+                .help("split lines into fixed-width columns at these START:END characters, to select with --fields")
+                .long("columns")
+                .value_name("COLUMNS")
+                .conflicts_with_all(&["widths", "regex_delim", "chars", "bytes"])
+        )
+        .arg(
+            Arg::new("trim")
+                .help("trim whitespace around each field, such as the padding of columns")
+                .long("trim")
+                .takes_value(false)
+                .conflicts_with_all(&["chars", "bytes"])
+        )
+        .arg(
+            Arg::new("only_delimited")
+                .help("do not print lines without the delimiter")
+                .short('s')
+                .long("only-delimited")
+                .takes_value(false)
+                .conflicts_with_all(&["chars", "bytes"])
+        )
+        .arg(
+            Arg::new("zero_terminated")
+                .help("lines end with NUL rather than newline")
+                .short('z')
+                .long("zero-terminated")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("csv")
+                .help("read and write fields as RFC 4180 CSV, by default separated by commas; with --widths or --columns, write it")
+                .long("csv")
+                .takes_value(false)
+                .conflicts_with_all(&["chars", "bytes"])
+        )
+        .arg(
+            Arg::new("quote_style")
+                .help("quote output fields always, when necessary or never")
+                .long("quote-style")
+                .value_name("STYLE")
+                .possible_values(["always", "necessary", "never"])
+                .default_value("necessary")
+                .requires("csv")
+        )
+        .arg(
+            Arg::new("quote")
+                .help("use CHAR to quote fields")
+                .long("quote")
+                .value_name("CHAR")
+                .default_value("\"")
+                .requires("csv")
+        )
+        .arg(
+            Arg::new("escape")
+                .help("use CHAR to escape quotes in quoted fields rather than doubling them")
+                .long("escape")
+                .value_name("CHAR")
+                .requires("csv")
+        )
+        .arg(
+            Arg::new("output_delimiter")
+                .help("use STR to join the output; by default fields are joined by DELIM and the rest by nothing")
+                .long("output-delimiter")
+                .value_name("STR")
+        )
+        .arg(
+            Arg::new("complement")
+                .help("select everything but the listed bytes, characters or fields")
+                .long("complement")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("reorder")
+                .help("output positions in the order listed, repeats included")
+                .long("reorder")
+                .takes_value(false)
+                .conflicts_with("complement")
+        )
+        .arg(
+            Arg::new("header")
+                .help("take the first row as column names, and output it too")
+                .long("header")
+                .takes_value(false)
+                .conflicts_with_all(&["chars", "bytes"])
+        )
+        .arg(
+            Arg::new("names")
+                .help("select the columns with these names, as globs or /regex/; implies --header")
+                .short('F')
+                .long("field-names")
+                .value_name("NAMES")
+                .conflicts_with_all(&["fields", "chars", "bytes"])
+        )
+        .arg(
+            Arg::new("no_split")
+                .help("with --bytes, do not split multibyte characters")
+                .short('n')
+                .takes_value(false)
+        )
         .arg(
             Arg::new("bytes")
                 .help("select only these bytes")
                 .short('b')
                 .long("bytes")
                 .value_name("BYTES")
-                .conflicts_with_all(&["fields", "chars"])
+                .allow_hyphen_values(true)
+                .conflicts_with_all(&["fields", "chars", "names"])
         )
         .arg(
             Arg::new("chars")
@@ -95,43 +489,140 @@ pub fn get_args() -> RetType<Config> {
                 .short('c')
                 .long("characters")
                 .value_name("CHARS")
-                .conflicts_with_all(&["fields", "bytes"])
+                .allow_hyphen_values(true)
+                .conflicts_with_all(&["fields", "bytes", "names"])
+        )
+        .arg(
+            Arg::new("graphemes")
+                .help("with --chars, count grapheme clusters rather than code points")
+                .long("graphemes")
+                .takes_value(false)
+                .conflicts_with_all(&["fields", "bytes", "names"])
+        )
+        .arg(
+            Arg::new("width")
+                .help("with --chars, count terminal columns; a wide character is kept if its last column is")
+                .long("width")
+                .takes_value(false)
+                .conflicts_with_all(&["fields", "bytes", "names", "graphemes"])
         )
         .arg(
             Arg::new("fields")
-                .help("select only these fields")
+                .help("select only these fields, or with --header the columns with these names")
                 .short('f')
                 .long("fields")
                 .value_name("FIELDS")
-                .conflicts_with_all(&["chars", "bytes"])
+                .allow_hyphen_values(true)
+                .conflicts_with_all(&["chars", "bytes", "names"])
         )
         .get_matches();
 
+    let single_byte = |flag: &str, val: &str| -> RetType<u8> {
+        match val.as_bytes() {
+            [byte] => Ok(*byte),
+            _ => Err(From::from(format!(
+                "--{} \"{}\" must be a single byte",
+                flag, val
+            ))),
+        }
+    };
+
+    let delimiter_given = matches.occurrences_of("delimiter") > 0;
     let delimiter = matches.remove_one::<String>("delimiter").unwrap();
-    let delim_bytes = delimiter.as_bytes();
-    if delimiter.is_empty() || delim_bytes.len() > 1 {
-        return Err(From::from(format!(
-            "--delim \"{}\" must be a single byte",
-            delimiter
-        )));
-    }
-
-    let fields = matches.value_of("fields").map(parse_pos).transpose()?;
-    let bytes = matches.value_of("bytes").map(parse_pos).transpose()?;
-    let chars = matches.value_of("chars").map(parse_pos).transpose()?;
-    let extract = if let Some(fields_pos) = fields {
-        Fields(fields_pos)
+    if delimiter.is_empty() {
+        return Err(From::from("--delim \"\" must not be empty"));
+    }
+    let output_delimiter = matches.remove_one::<String>("output_delimiter");
+    let csv = if matches.is_present("csv") {
+        if let Some(delim) = &output_delimiter {
+            single_byte("output-delimiter", delim)?;
+        }
+        Some(CsvStyle {
+            // CSV is separated by commas unless told otherwise.
+            delimiter: if delimiter_given {
+                single_byte("delim", &delimiter)?
+            } else {
+                b','
+            },
+            quote_style: match matches.value_of("quote_style") {
+                Some("always") => QuoteStyle::Always,
+                Some("never") => QuoteStyle::Never,
+                _ => QuoteStyle::Necessary,
+            },
+            quote: single_byte("quote", matches.value_of("quote").unwrap())?,
+            escape: matches.value_of("escape").map(|e| single_byte("escape", e)).transpose()?,
+        })
+    } else {
+        None
+    };
+
+    let widths = matches.value_of("widths").map(parse_widths).transpose()?;
+    let columns = matches.value_of("columns").map(parse_columns).transpose()?;
+    let delimiter = match matches.value_of("regex_delim") {
+        _ if widths.is_some() || columns.is_some() => {
+            Delimiter::Columns(widths.or(columns).unwrap())
+        }
+        Some(re) => {
+            let re = bytes::Regex::new(re)
+                .map_err(|e| format!("--regex-delim \"{}\": {}", re, e))?;
+            if re.is_match(b"") {
+                return Err(From::from(format!(
+                    "--regex-delim \"{}\" must not match an empty string",
+                    re
+                )));
+            }
+            Delimiter::Pattern(re)
+        }
+        None => Delimiter::Literal(delimiter),
+    };
+
+    let reorder = matches.is_present("reorder");
+    let names = matches.value_of("names").map(parse_names).transpose()?;
+    let header = names.is_some() || matches.is_present("header");
+    let parse = |list| parse_pos(list, reorder);
+    // With a header, a field list that isn't positions is taken as names.
+    let fields = match matches.value_of("fields") {
+        Some(list) if header => Some(parse(list).map(Fields).or_else(|e| {
+            parse_names(list).map(Names).map_err(|_| e)
+        })?),
+        list => list.map(parse).transpose()?.map(Fields),
+    };
+    let bytes = matches.value_of("bytes").map(parse).transpose()?;
+    let chars = matches.value_of("chars").map(parse).transpose()?;
+    let extract = if let Some(names) = names {
+        Names(names)
+    } else if let Some(fields) = fields {
+        fields
     } else if let Some(byte_pos) = bytes {
         Bytes(byte_pos)
     } else if let Some(char_pos) = chars {
         Chars(char_pos)
+    } else if let Delimiter::Columns(_) = delimiter {
+        // All of the columns, unless some are chosen.
+        Fields(vec![Span { start: 0, end: None }])
     } else {
         return Err(From::from("Must have --fields, --bytes, or --chars"));
     };
 
     Ok(Config {
         files: matches.remove_many::<String>("files").unwrap().collect(),
-        delimiter: delim_bytes[0],
+        delimiter,
+        output_delimiter,
+        complement: matches.is_present("complement"),
+        reorder,
+        header,
+        csv,
+        only_delimited: matches.is_present("only_delimited"),
+        trim: matches.is_present("trim"),
+        no_split: matches.is_present("no_split"),
+        char_unit: if matches.is_present("width") {
+            CharUnit::Width
+        } else if matches.is_present("graphemes") {
+            CharUnit::Grapheme
+        } else {
+            CharUnit::Scalar
+        },
+        terminator: if matches.is_present("zero_terminated") { b'\0' } else { b'\n' },
         extract
     })
 }
@@ -143,70 +634,263 @@ fn open(filename: &str) -> RetType<Box< dyn BufRead>> {
     }
 }
 
-fn extract_chars(line: &str, char_pos: &[usize]) -> String {
+// The lines of `file` ending with `terminator`, which is left off. Bytes
+// are kept exactly as they are, carriage returns included.
+fn byte_lines(file: Box<dyn BufRead>, terminator: u8) -> impl Iterator<Item = io::Result<Vec<u8>>> {
+    file.split(terminator)
+}
+
+// As `byte_lines`, but without the carriage return of a CRLF.
+fn text_lines(file: Box<dyn BufRead>, terminator: u8) -> impl Iterator<Item = io::Result<Vec<u8>>> {
+    byte_lines(file, terminator).map(move |line| {
+        let mut line = line?;
+        if terminator == b'\n' && line.ends_with(b"\r") {
+            line.pop();
+        }
+        Ok(line)
+    })
+}
+
+fn lines(file: Box<dyn BufRead>, terminator: u8) -> impl Iterator<Item = RetType<String>> {
+    text_lines(file, terminator).map(|line| Ok(String::from_utf8(line?)?))
+}
+
+fn print_line(line: &str, terminator: u8) {
+    print!("{}{}", line, terminator as char);
+}
+
+// Runs of selected characters or bytes are joined by `joiner`, as in cut.
+fn extract_chars(line: &str, char_pos: &[Span], complement: bool, unit: CharUnit, joiner: &str) -> String {
     if line.is_empty() {
         return String::from("")
     }
 
-    let line_vec = line.chars().collect::<Vec<_>>();
-    char_pos.iter()
-        .filter_map(|pos| line_vec.get(*pos))
-        .collect::<String>()
+    // Each piece of the line with the position it is selected by: its index,
+    // or by width the last column it takes up.
+    let pieces: Vec<(usize, &str)> = match unit {
+        CharUnit::Scalar => line
+            .char_indices()
+            .map(|(i, c)| &line[i..i + c.len_utf8()])
+            .enumerate()
+            .collect(),
+        CharUnit::Grapheme => line.graphemes(true).enumerate().collect(),
+        CharUnit::Width => {
+            let mut column = 0;
+            line.graphemes(true)
+                .map(|g| {
+                    column += g.width();
+                    (column.max(1) - 1, g)
+                })
+                .collect()
+        }
+    };
+    let len = pieces.last().map_or(0, |(pos, _)| pos + 1);
+    ranges(char_pos, len, complement)
+        .into_iter()
+        .map(|range| {
+            pieces
+                .iter()
+                .filter(|(pos, _)| range.contains(pos))
+                .map(|(_, piece)| *piece)
+                .collect::<String>()
+        })
+        .filter(|run| !run.is_empty())
+        .collect::<Vec<_>>()
+        .join(joiner)
 }
 
-fn extract_bytes(line: &str, byte_pos: &[usize]) -> String {
-    if line.is_empty() {
-        return String::from("")
+// With `no_split`, both ends of each run move back to where a UTF-8
+// character starts, so that a character is kept when its last byte is
+// selected, as POSIX has it for `cut -n`.
+fn extract_bytes(bytes: &[u8], byte_pos: &[Span], complement: bool, no_split: bool, joiner: &[u8]) -> Vec<u8> {
+    if bytes.is_empty() {
+        return vec![]
     }
 
-    let bytes = line.as_bytes();
-    let selected: Vec<u8> = byte_pos.iter()
-        .filter_map(|i| bytes.get(*i)) 
-        .cloned()
-        .collect();
-    String::from_utf8_lossy(&selected).into_owned()
+    let char_start = |mut i: usize| {
+        while no_split && i > 0 && i < bytes.len() && bytes[i] & 0xC0 == 0x80 {
+            i -= 1;
+        }
+        i
+    };
+    ranges(byte_pos, bytes.len(), complement)
+        .into_iter()
+        .map(|range| char_start(range.start)..char_start(range.end))
+        .filter(|range| !range.is_empty())
+        .map(|range| &bytes[range])
+        .collect::<Vec<_>>()
+        .join(joiner)
 }
 
-fn extract_fields<'a>(record: &'a StringRecord, field_pos: &'a PositionList) -> Vec<&'a str> {
-    field_pos
-        .iter()
-        .filter_map(|i| record.get(*i))
+fn extract_fields<'a, T: ?Sized>(record: &[&'a T], field_pos: &[Span], complement: bool) -> Vec<&'a T> {
+    ranges(field_pos, record.len(), complement)
+        .into_iter()
+        .flatten()
+        .map(|i| record[i])
         .collect()
 }
 
+// The fields to select from a file with `headers`, which an unmatched name
+// makes it skip. An empty file has no headers, so no name matches.
+fn field_list(config: &Config, headers: Option<&[&str]>) -> RetType<PositionList> {
+    match &config.extract {
+        Names(names) => name_spans(names, headers.unwrap_or_default(), config.reorder),
+        Fields(list) | Bytes(list) | Chars(list) => Ok(list.clone()),
+    }
+}
+
+fn split_fields<'a>(config: &Config, line: &'a [u8]) -> Vec<&'a [u8]> {
+    let fields = config.delimiter.split(line);
+    if config.trim {
+        fields.into_iter().map(<[u8]>::trim_ascii).collect()
+    } else {
+        fields
+    }
+}
+
+// Splits each line at the delimiter as cut does, leaving quotes alone.
+// Lines without the delimiter are passed through whole. With --csv, which
+// only gets here for fixed-width columns, the fields are written as CSV.
+fn cut_fields(config: &Config, filename: &str, file: Box<dyn BufRead>) -> RetType<()> {
+    let delim = &config.delimiter;
+    let joiner = config.output_delimiter.as_deref().unwrap_or(delim.joiner()).as_bytes();
+
+    let mut lines = text_lines(file, config.terminator);
+    let headers = if config.header { lines.next().transpose()? } else { None };
+    // Names are matched as text, whatever the encoding of the header.
+    let names: Option<Vec<Cow<str>>> = headers
+        .as_deref()
+        .map(|h| split_fields(config, h).into_iter().map(String::from_utf8_lossy).collect());
+    let names: Option<Vec<&str>> = names.as_ref().map(|h| h.iter().map(AsRef::as_ref).collect());
+    let list = match field_list(config, names.as_deref()) {
+        Ok(list) => list,
+        Err(e) => {
+            eprintln!("{}: {}", filename, e);
+            return Ok(());
+        }
+    };
+
+    let mut wtr = config.csv.as_ref().map(|style| csv_writer(config, style));
+    let mut out = io::stdout().lock();
+    for line in headers.map(Ok).into_iter().chain(lines) {
+        let line = line?;
+        if !delim.is_in(&line) {
+            if !config.only_delimited {
+                out.write_all(&line)?;
+                out.write_all(&[config.terminator])?;
+            }
+            continue;
+        }
+        let record = split_fields(config, &line);
+        let fields = extract_fields(&record, &list, config.complement);
+        match &mut wtr {
+            Some(wtr) => wtr.write_record(fields)?,
+            None => {
+                out.write_all(&fields.join(joiner))?;
+                out.write_all(&[config.terminator])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reads and writes fields as CSV, so that quoted fields may hold the
+// delimiter, quotes and newlines. Records of one field are passed through
+// whole, as lines without the delimiter are.
+fn cut_csv(config: &Config, style: &CsvStyle, filename: &str, file: Box<dyn BufRead>) -> RetType<()> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(style.delimiter)
+        .has_headers(config.header)
+        .flexible(true)
+        .quote(style.quote)
+        .escape(style.escape)
+        .terminator(reader_terminator(config.terminator))
+        .from_reader(file);
+
+    // An empty file has no header row to write out.
+    let headers = if config.header {
+        Some(reader.headers()?.clone()).filter(|headers| !headers.is_empty())
+    } else {
+        None
+    };
+    let names: Option<Vec<_>> = headers.as_ref().map(|h| h.iter().collect());
+    let list = match field_list(config, names.as_deref()) {
+        Ok(list) => list,
+        Err(e) => {
+            eprintln!("{}: {}", filename, e);
+            return Ok(());
+        }
+    };
+
+    let mut wtr = csv_writer(config, style);
+    for record in headers.map(Ok).into_iter().chain(reader.records()) {
+        let record = record?;
+        if record.len() < 2 {
+            if !config.only_delimited {
+                wtr.write_record(&record)?;
+            }
+            continue;
+        }
+        let fields: Vec<_> = if config.trim {
+            record.iter().map(str::trim).collect()
+        } else {
+            record.iter().collect()
+        };
+        wtr.write_record(extract_fields(&fields, &list, config.complement))?;
+    }
+    Ok(())
+}
+
+fn csv_writer(config: &Config, style: &CsvStyle) -> csv::Writer<io::Stdout> {
+    let out_delim = match &config.output_delimiter {
+        Some(delim) => delim.as_bytes()[0],
+        None => style.delimiter,
+    };
+    WriterBuilder::new()
+        .delimiter(out_delim)
+        .quote_style(style.quote_style)
+        .quote(style.quote)
+        .escape(style.escape.unwrap_or(b'\\'))
+        .double_quote(style.escape.is_none())
+        .flexible(true)
+        .terminator(Terminator::Any(config.terminator))
+        .from_writer(io::stdout())
+}
+
+// Newlines end records as CR, LF or CRLF, as the csv reader has them.
+fn reader_terminator(terminator: u8) -> Terminator {
+    match terminator {
+        b'\n' => Terminator::CRLF,
+        byte => Terminator::Any(byte),
+    }
+}
+
 pub fn run(config: Config) -> RetType<()> {
+    let joiner = config.output_delimiter.as_deref().unwrap_or("");
     for filename in &config.files    {
         match open(filename) {
             Err(err) => eprintln!("{}: {}", filename, err),
             Ok(file) => {
                 match &config.extract {
                     Bytes(list) => {
-                        for line in file.lines() {
-                            let bytes = extract_bytes(line?.as_str(), &list);
-                            println!("{}", bytes);
+                        // Bytes are written as they are, even if they aren't UTF-8.
+                        let mut out = io::stdout().lock();
+                        for line in byte_lines(file, config.terminator) {
+                            let bytes = extract_bytes(&line?, list, config.complement, config.no_split, joiner.as_bytes());
+                            out.write_all(&bytes)?;
+                            out.write_all(&[config.terminator])?;
                         }
                     }
                     Chars(list) => {
-                        for line in file.lines() {
-                            let chars = extract_chars(line?.as_str(), &list);
-                            println!("{}", chars);
-                        }
-                    }
-                    Fields(list) => {
-                        let mut reader = ReaderBuilder::new()
-                            .delimiter(config.delimiter)
-                            .has_headers(false)
-                            .from_reader(file);
-
-                        let mut wtr = WriterBuilder::new()
-                            .delimiter(config.delimiter)
-                            .from_writer(io::stdout());
-
-                        for record in reader.records() {
-                            let record = record?;
-                            wtr.write_record(extract_fields(&record, &list))?;
+                        for line in lines(file, config.terminator) {
+                            let chars = extract_chars(line?.as_str(), list, config.complement, config.char_unit, joiner);
+                            print_line(&chars, config.terminator);
                         }
                     }
+                    Fields(_) | Names(_) => match (&config.csv, &config.delimiter) {
+                        (Some(style), Delimiter::Literal(_)) => cut_csv(&config, style, filename, file)?,
+                        _ => cut_fields(&config, filename, file)?,
+                    },
                 };
             }
         }
@@ -1,5 +1,3 @@
-pub mod lib;
-
 fn main() {
     if let Err(e) = cutr::get_args().and_then(cutr::run) {
         eprintln!("{}", e);
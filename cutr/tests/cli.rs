@@ -35,7 +35,7 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["-f", "1", CSV, &bad, TSV])
+        .args(["-f", "1", CSV, &bad, TSV])
         .assert()
         .success()
         .stderr(predicate::str::is_match(expected)?);
@@ -88,12 +88,57 @@ fn dies_bad_digit_chars() -> TestResult {
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_zero_position() -> TestResult {
+    for (list, bad) in
+        [("0", "0"), ("0-2", "0-2"), ("2-0", "2-0"), ("1,0", "0")]
+    {
+        dies(
+            &[CSV, "-f", list],
+            &format!(
+                "illegal list value: \"{}\": positions are numbered from 1",
+                bad
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_malformed_list() -> TestResult {
+    for (list, bad) in [
+        ("-", "-"),
+        ("1,,2", ""),
+        ("1-2-3", "1-2-3"),
+        ("+1", "+1"),
+        ("1,a-2", "a-2"),
+    ] {
+        dies(
+            &[CSV, "-b", list],
+            &format!("illegal list value: \"{}\"", bad),
+        )?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_decreasing_range() -> TestResult {
+    dies(
+        &[CSV, "-c", "5-3"],
+        "First number in range (5) must not be greater than the second \
+        number (3)",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_empty_delimiter() -> TestResult {
     dies(
         &[CSV, "-f", "1", "-d", ""],
-        "--delim \"\" must be a single byte",
+        "--delim \"\" must not be empty",
     )
 }
 
@@ -101,7 +146,7 @@ fn dies_empty_delimiter() -> TestResult {
 #[test]
 fn dies_bad_delimiter() -> TestResult {
     dies(
-        &[CSV, "-f", "1", "-d", ",,"],
+        &[CSV, "--csv", "-f", "1", "-d", ",,"],
         "--delim \",,\" must be a single byte",
     )
 }
@@ -110,7 +155,7 @@ fn dies_bad_delimiter() -> TestResult {
 #[test]
 fn dies_chars_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -120,7 +165,7 @@ fn dies_chars_bytes_fields() -> TestResult {
 #[test]
 fn dies_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-f", "1", "-b", "1"])
+        .args([CSV, "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -130,7 +175,7 @@ fn dies_bytes_fields() -> TestResult {
 #[test]
 fn dies_chars_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1"])
+        .args([CSV, "-c", "1", "-f", "1"])
         .assert()
         .failure();
     Ok(())
@@ -140,7 +185,7 @@ fn dies_chars_fields() -> TestResult {
 #[test]
 fn dies_chars_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -149,7 +194,7 @@ fn dies_chars_bytes() -> TestResult {
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     println!("expected {}", &expected_file);
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
@@ -158,18 +203,6 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
-// --------------------------------------------------
-fn run_lossy(args: &[&str], expected_file: &str) -> TestResult {
-    let contents = fs::read(expected_file)?;
-    let expected = String::from_utf8_lossy(&contents);
-    Command::cargo_bin(PRG)?
-        .args(args)
-        .assert()
-        .success()
-        .stdout(predicate::str::contains(expected));
-    Ok(())
-}
-
 // --------------------------------------------------
 #[test]
 fn tsv_f1() -> TestResult {
@@ -206,6 +239,39 @@ fn tsv_f1_3() -> TestResult {
     run(&[TSV, "-f", "1-3"], "tests/expected/movies1.tsv.f1-3.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn tsv_f2_open() -> TestResult {
+    run(&[TSV, "-f", "2-"], "tests/expected/movies1.tsv.f2-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_open_2() -> TestResult {
+    run(&[TSV, "-f", "-2"], "tests/expected/movies1.tsv.f-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_2() -> TestResult {
+    run(&[TSV, "-f", "2-2"], "tests/expected/movies1.tsv.f2-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1() -> TestResult {
+    run(&[TSV, "-f", "3,1"], "tests/expected/movies1.tsv.f3,1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_overlapping() -> TestResult {
+    run(
+        &[TSV, "-f", "1-2,2-3"],
+        "tests/expected/movies1.tsv.f1-2,2-3.out",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 fn csv_f1() -> TestResult {
@@ -275,7 +341,7 @@ fn tsv_b2() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn tsv_b8() -> TestResult {
-    run_lossy(&[TSV, "-b", "8"], "tests/expected/movies1.tsv.b8.out")
+    run(&[TSV, "-b", "8"], "tests/expected/movies1.tsv.b8.out")
 }
 
 // --------------------------------------------------
@@ -293,7 +359,13 @@ fn tsv_b2_3() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn tsv_b1_8() -> TestResult {
-    run_lossy(&[TSV, "-b", "1-8"], "tests/expected/movies1.tsv.b1-8.out")
+    run(&[TSV, "-b", "1-8"], "tests/expected/movies1.tsv.b1-8.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b_open_3() -> TestResult {
+    run(&[TSV, "-b", "-3"], "tests/expected/movies1.tsv.b-3.out")
 }
 
 // --------------------------------------------------
@@ -332,9 +404,480 @@ fn tsv_c1_8() -> TestResult {
     run(&[TSV, "-c", "1-8"], "tests/expected/movies1.tsv.c1-8.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn tsv_c8_open() -> TestResult {
+    run(&[TSV, "-c", "8-"], "tests/expected/movies1.tsv.c8-.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn repeated_value() -> TestResult {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn tsv_f2_complement() -> TestResult {
+    run(
+        &[TSV, "-f", "2", "--complement"],
+        "tests/expected/movies1.tsv.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b2_3_complement() -> TestResult {
+    run(
+        &[TSV, "-b", "2-3", "--complement"],
+        "tests/expected/movies1.tsv.b2-3.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f1_3_output_delimiter() -> TestResult {
+    run(
+        &[TSV, "-f", "1,3", "--output-delimiter", "|"],
+        "tests/expected/movies1.tsv.f1,3.dpipe.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f1_3_long_output_delimiter() -> TestResult {
+    run(
+        &[TSV, "-f", "1,3", "--output-delimiter", " | "],
+        "tests/expected/movies1.tsv.f1,3.dbar.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_output_delimiter() -> TestResult {
+    run(
+        &[TSV, "-c", "1-3,6-", "--output-delimiter", " | "],
+        "tests/expected/movies1.tsv.c1-3,6-.dbar.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_2_reorder() -> TestResult {
+    run(
+        &[TSV, "-f", "3,1,2", "--reorder"],
+        "tests/expected/movies1.tsv.f3,1,2.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f1_1_reorder() -> TestResult {
+    run(
+        &[TSV, "-f", "1,1,2-", "--reorder"],
+        "tests/expected/movies1.tsv.f1,1,2-.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_reorder_complement() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TSV, "-f", "1", "--reorder", "--complement"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_field_names() -> TestResult {
+    run(
+        &[CSV, "-d", ",", "-F", "year,title"],
+        "tests/expected/movies1.csv.Fyear,title.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_field_patterns_reorder() -> TestResult {
+    run(
+        &[
+            CSV,
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "y*,/^t/",
+            "--reorder",
+            "--output-delimiter",
+            " | ",
+        ],
+        "tests/expected/movies1.csv.fy,t.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_header_field_glob() -> TestResult {
+    run(
+        &[TSV, "--header", "-f", "dir*"],
+        "tests/expected/movies1.tsv.fdir.header.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_unmatched_name() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-F", "nope", TSV])
+        .assert()
+        .success()
+        .stderr(format!("{}: no column matches \"nope\"\n", TSV));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn names_on_empty_input() -> TestResult {
+    for args in [
+        &["-F", "year"][..],
+        &["--header", "-f", "year"],
+        &["--csv", "-F", "year"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin("")
+            .assert()
+            .success()
+            .stdout("")
+            .stderr("-: no column matches \"year\"\n");
+    }
+    for args in [
+        &["--header", "-f", "1"][..],
+        &["--csv", "--header", "-f", "1"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin("")
+            .assert()
+            .success()
+            .stdout("")
+            .stderr("");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_name_without_header() -> TestResult {
+    dies(&[TSV, "-f", "title"], "illegal list value: \"title\"")
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_raw_keeps_quotes() -> TestResult {
+    run(
+        &["tests/inputs/movies2.csv", "-d", ",", "-f", "1"],
+        "tests/expected/movies2.csv.f1.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn passes_lines_without_delimiter() -> TestResult {
+    run(
+        &["tests/inputs/books.txt", "-d", ",", "-f", "2"],
+        "tests/expected/books.txt.f2.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode() -> TestResult {
+    run(
+        &["tests/inputs/movies2.csv", "--csv", "-f", "1,3"],
+        "tests/expected/movies2.csv.f1,3.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode_quote_always() -> TestResult {
+    run(
+        &[
+            "tests/inputs/movies2.csv",
+            "--csv",
+            "-f",
+            "1",
+            "--quote-style",
+            "always",
+        ],
+        "tests/expected/movies2.csv.f1.always.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode_embedded_newlines() -> TestResult {
+    run(
+        &[
+            "tests/inputs/movies3.csv",
+            "--csv",
+            "-f",
+            "3,1",
+            "--reorder",
+        ],
+        "tests/expected/movies3.csv.f3,1.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode_quote_char() -> TestResult {
+    run(
+        &[
+            "tests/inputs/movies.squote.csv",
+            "--csv",
+            "--quote",
+            "'",
+            "-f",
+            "1,3",
+        ],
+        "tests/expected/movies.squote.csv.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode_escape_char() -> TestResult {
+    run(
+        &[
+            "tests/inputs/movies.escape.csv",
+            "--csv",
+            "--escape",
+            "\\",
+            "-f",
+            "1",
+        ],
+        "tests/expected/movies.escape.csv.f1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_quote_without_csv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-f", "1", "--quote", "'"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_csv_long_output_delimiter() -> TestResult {
+    dies(
+        &[CSV, "--csv", "-f", "1", "--output-delimiter", "::"],
+        "--output-delimiter \"::\" must be a single byte",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn only_delimited() -> TestResult {
+    run(
+        &[
+            "-d",
+            ",",
+            "-s",
+            "-f",
+            "2",
+            "tests/inputs/books.txt",
+            "tests/inputs/books.csv",
+        ],
+        "tests/expected/books.f2.dcomma.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_mode_only_delimited() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--csv", "-s", "-f", "2"])
+        .write_stdin("title\n\"a,b\",c\n")
+        .assert()
+        .success()
+        .stdout("c\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    run(
+        &["-z", "-f", "2", "tests/inputs/zero.tsv"],
+        "tests/expected/zero.tsv.f2.z.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_only_delimited() -> TestResult {
+    run(
+        &["-z", "-s", "-f", "2", "tests/inputs/zero.tsv"],
+        "tests/expected/zero.tsv.f2.z.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multi_byte_delimiter() -> TestResult {
+    run(
+        &["tests/inputs/movies1.dcolons", "-d", "::", "-f", "1,3"],
+        "tests/expected/movies1.dcolons.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delimiter() -> TestResult {
+    run(
+        &["tests/inputs/ps.txt", "--regex-delim", r"\s+", "-f", "1,4"],
+        "tests/expected/ps.txt.f1,4.regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regex_delimiter() -> TestResult {
+    dies(
+        &[TSV, "-f", "1", "--regex-delim", "("],
+        "--regex-delim \"(\":",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_empty_regex_delimiter() -> TestResult {
+    dies(
+        &[TSV, "-f", "1", "--regex-delim", r"\s*"],
+        r#"--regex-delim "\s*" must not match an empty string"#,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes_kept_raw() -> TestResult {
+    run(
+        &["tests/inputs/latin1.txt", "-b", "4-8"],
+        "tests/expected/latin1.txt.b4-8.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fields_kept_raw() -> TestResult {
+    run(
+        &["tests/inputs/latin1.txt", "-d", " ", "-f", "2"],
+        "tests/expected/latin1.txt.f2.dspace.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes_keep_carriage_returns() -> TestResult {
+    run(
+        &["tests/inputs/crlf.txt", "-b", "2-"],
+        "tests/expected/crlf.txt.b2-.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes_no_split() -> TestResult {
+    run(
+        &["tests/inputs/multibyte.txt", "-n", "-b", "1-2"],
+        "tests/expected/multibyte.txt.b1-2.n.out",
+    )?;
+    run(
+        &["tests/inputs/multibyte.txt", "-n", "-b", "3-"],
+        "tests/expected/multibyte.txt.b3-.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn graphemes() -> TestResult {
+    run(
+        &["tests/inputs/names.txt", "--graphemes", "-c", "1-3"],
+        "tests/expected/names.txt.c1-3.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn display_width() -> TestResult {
+    run(
+        &["tests/inputs/names.txt", "--width", "-c", "1-3"],
+        "tests/expected/names.txt.c1-3.width.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_widths() -> TestResult {
+    run(
+        &["tests/inputs/report.txt", "--widths", "5,10,8"],
+        "tests/expected/report.txt.widths.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_widths_names_trimmed() -> TestResult {
+    run(
+        &[
+            "tests/inputs/report.txt",
+            "--widths",
+            "5,10,8",
+            "--trim",
+            "-F",
+            "CITY,NAME",
+            "--reorder",
+        ],
+        "tests/expected/report.txt.widths.names.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_columns_csv() -> TestResult {
+    run(
+        &[
+            "tests/inputs/report.txt",
+            "--columns",
+            "6:15,16:",
+            "--trim",
+            "--csv",
+        ],
+        "tests/expected/report.txt.columns.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_widths() -> TestResult {
+    dies(&[TSV, "--widths", "5,0"], "illegal width: \"0\"")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_columns() -> TestResult {
+    dies(&[TSV, "--columns", "1-5"], "illegal column: \"1-5\"")?;
+    dies(
+        &[TSV, "--columns", "5:3"],
+        "First number in range (5) must not be greater than the second \
+        number (3)",
+    )
+}
@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::Hash,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+use crate::{errors::{EntryError, Errors}, RetType};
+
+// Files no bigger than this are fully compared by the first hash.
+const BLOCK: u64 = 4096;
+
+/// Prints every group of regular files with identical contents, one path per
+/// line and a blank line between groups. Candidates are split up by size,
+/// then by a hash of their first block, and only the files still sharing a
+/// group after that are hashed in full. Empty files are left out.
+pub fn print_duplicates(
+    paths: Vec<PathBuf>,
+    errors: &Errors,
+    out: &mut impl Write,
+) -> RetType<()> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_file() && meta.len() > 0 => {
+                by_size.entry(meta.len()).or_default().push(path)
+            }
+            Ok(_) => {}
+            Err(e) => errors.report(EntryError::new(&path, e)),
+        }
+    }
+
+    let mut groups = vec![];
+    for (size, paths) in by_size {
+        if paths.len() < 2 {
+            continue;
+        }
+        for group in group_by(paths, hash_head, errors) {
+            if size <= BLOCK {
+                groups.push(group);
+            } else {
+                groups.extend(group_by(group, hash_all, errors));
+            }
+        }
+    }
+
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups.sort();
+
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        for path in group {
+            writeln!(out, "{}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+// Splits `paths` by `key`, keeping only groups of two or more.
+fn group_by<K, F>(paths: Vec<PathBuf>, key: F, errors: &Errors) -> Vec<Vec<PathBuf>>
+where
+    K: Hash + Eq,
+    F: Fn(&Path) -> io::Result<K>,
+{
+    let mut groups: HashMap<K, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        match key(&path) {
+            Ok(k) => groups.entry(k).or_default().push(path),
+            Err(e) => errors.report(EntryError::new(&path, e)),
+        }
+    }
+    groups.into_values().filter(|group| group.len() > 1).collect()
+}
+
+fn hash_head(path: &Path) -> io::Result<blake3::Hash> {
+    let mut head = vec![];
+    File::open(path)?.take(BLOCK).read_to_end(&mut head)?;
+    Ok(blake3::hash(&head))
+}
+
+fn hash_all(path: &Path) -> io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize())
+}
@@ -0,0 +1,70 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// An entry that couldn't be read, and why.
+#[derive(Debug)]
+pub struct EntryError {
+    path: PathBuf,
+    reason: String,
+}
+
+impl EntryError {
+    pub fn new(path: &Path, reason: impl fmt::Display) -> EntryError {
+        EntryError { path: path.to_path_buf(), reason: reason.to_string() }
+    }
+
+    /// A directory that is the same as `ancestor`, which contains it.
+    pub fn fs_loop(path: &Path, ancestor: &Path) -> EntryError {
+        EntryError::new(
+            path,
+            format!("File system loop detected, it is the same directory as {}", ancestor.display()),
+        )
+    }
+}
+
+impl From<walkdir::Error> for EntryError {
+    fn from(err: walkdir::Error) -> EntryError {
+        let path = err.path().unwrap_or(Path::new(""));
+        match (err.loop_ancestor(), err.io_error()) {
+            (Some(ancestor), _) => EntryError::fs_loop(path, ancestor),
+            (_, Some(e)) => EntryError::new(path, e),
+            _ => EntryError::new(path, &err),
+        }
+    }
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
+}
+
+impl std::error::Error for EntryError {}
+
+/// Reports entries that couldn't be read on stderr, unless --quiet-errors,
+/// and remembers that there were any so that findr can exit with 1, as find
+/// does. The walk carries on either way.
+pub struct Errors {
+    quiet: bool,
+    seen: AtomicBool,
+}
+
+impl Errors {
+    pub fn new(quiet: bool) -> Errors {
+        Errors { quiet, seen: AtomicBool::new(false) }
+    }
+
+    pub fn report(&self, err: EntryError) {
+        self.seen.store(true, Ordering::Relaxed);
+        if !self.quiet {
+            eprintln!("{}", err);
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.seen.load(Ordering::Relaxed)
+    }
+}
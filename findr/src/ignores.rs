@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use ignore::{gitignore::{Gitignore, GitignoreBuilder}, Match};
+
+// The ignore rules found in one directory.
+struct DirRules {
+    rules: Gitignore,
+    is_repo_root: bool,
+}
+
+/// The `--gitignore` rules for the walk below one start path. An entry is
+/// checked against the `.gitignore` and `.ignore` files of each directory
+/// from its parent up to the repository root, closest first, and then
+/// against the global excludes file.
+pub struct Ignores {
+    root: PathBuf,
+    base: PathBuf,
+    global: Gitignore,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirRules>>>,
+}
+
+impl Ignores {
+    pub fn new(root: &Path) -> Ignores {
+        Ignores {
+            root: root.to_path_buf(),
+            base: fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()),
+            global: Gitignore::global().0,
+            dirs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+
+        // Rules are rooted at absolute directories, so match absolute paths.
+        let path = self.base.join(path.strip_prefix(&self.root).unwrap_or(path));
+        for dir in path.ancestors().skip(1) {
+            let dir_rules = self.rules_for(dir);
+            match dir_rules.rules.matched(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+            if dir_rules.is_repo_root {
+                break;
+            }
+        }
+        self.global.matched(&path, is_dir).is_ignore()
+    }
+
+    fn rules_for(&self, dir: &Path) -> Arc<DirRules> {
+        let mut dirs = self.dirs.lock().unwrap();
+        if let Some(dir_rules) = dirs.get(dir) {
+            return Arc::clone(dir_rules);
+        }
+
+        let is_repo_root = dir.join(".git").exists();
+        let mut builder = GitignoreBuilder::new(dir);
+        if is_repo_root {
+            builder.add(dir.join(".git/info/exclude"));
+        }
+        // Later files win, so `.ignore` overrides `.gitignore`.
+        builder.add(dir.join(".gitignore"));
+        builder.add(dir.join(".ignore"));
+
+        let dir_rules = Arc::new(DirRules {
+            rules: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            is_repo_root,
+        });
+        dirs.insert(dir.to_path_buf(), Arc::clone(&dir_rules));
+        dir_rules
+    }
+}
@@ -1,7 +1,31 @@
-use std::{error::Error, fs, path::Path};
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use clap::{App, Arg};
-use regex::Regex;
+use regex::{bytes, Regex};
 use walkdir::{WalkDir, DirEntry};
+use errors::{EntryError, Errors};
+use ignores::Ignores;
+use listing::Listing;
+use perm::{Owner, Perm};
+use tree::TreeStyle;
+use walk::Follow;
+
+mod dupes;
+mod errors;
+mod ignores;
+mod listing;
+mod perm;
+mod pick;
+mod times;
+mod tree;
+mod walk;
+mod watch;
 
 type RetType<T> = Result<T, Box<dyn Error>>;
 
@@ -9,7 +33,27 @@ type RetType<T> = Result<T, Box<dyn Error>>;
 enum EntryType {
     Dir,
     File,
-    Link
+    Link,
+    Block,
+    Char,
+    Fifo,
+    Socket,
+}
+
+// The order of --sort. Only names can be sorted while walking.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SortKey {
+    Name,
+    Size,
+    Mtime,
+}
+
+// How each matching entry is printed.
+#[derive(Debug, PartialEq)]
+enum Format {
+    Path,
+    Json,
+    Ls,
 }
 
 #[derive(Debug)]
@@ -17,8 +61,31 @@ pub struct Config {
     dirs: Vec<String>,
     names: Option<Vec<Regex>>,
     entry_types: Option<Vec<EntryType>>,
+    xtypes: Option<Vec<EntryType>>,
+    empty: bool,
+    newer: Option<SystemTime>,
+    older: Option<SystemTime>,
+    jobs: usize,
+    follow: Follow,
+    sort: Option<SortKey>,
+    reverse: bool,
+    limit: Option<usize>,
+    perm: Option<Perm>,
+    owner: Owner,
+    gitignore: bool,
+    hidden: bool,
+    duplicates: bool,
+    format: Format,
+    tree: bool,
+    du: bool,
+    pick: bool,
+    watch: bool,
+    contains: Option<bytes::Regex>,
+    quiet_errors: bool,
 }
 
+const TYPES: [&str; 7] = ["f", "d", "l", "b", "c", "p", "s"];
+
 pub fn get_args() -> RetType<Config> {
     let matches = App::new("findr")
         .version("0.1.0")
@@ -42,6 +109,13 @@ pub fn get_args() -> RetType<Config> {
                 .takes_value(true)
                 .multiple(true)
         )
+        .arg(
+            Arg::new("contains")
+                .long("contains")
+                .value_name("REGEX")
+                .help("Match regular files with a line matching REGEX")
+                .takes_value(true)
+        )
         .arg(
             Arg::new("types")
                 .short('t')
@@ -49,9 +123,203 @@ pub fn get_args() -> RetType<Config> {
                 .value_name("TYPE")
                 .takes_value(true)
                 .allow_invalid_utf8(true)
-                .possible_values(&["f", "d", "l"])
+                .possible_values(TYPES)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new("xtypes")
+                .long("xtype")
+                .value_name("TYPE")
+                .help("Like --type, but for the target of symlinks; l matches broken ones")
+                .takes_value(true)
+                .allow_invalid_utf8(true)
+                .possible_values(TYPES)
                 .multiple(true)
         )
+        .arg(
+            Arg::new("broken")
+                .long("broken")
+                .help("Match symlinks whose target does not exist, same as --xtype l")
+                .takes_value(false)
+                .conflicts_with("xtypes")
+        )
+        .arg(
+            Arg::new("empty")
+                .long("empty")
+                .help("Match empty files and directories")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("newer_than")
+                .long("newer-than")
+                .value_name("DATE")
+                .help("Match entries modified after DATE, e.g. 2024-05-01T12:00 or 2h (ago)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("older_than")
+                .long("older-than")
+                .value_name("DATE")
+                .help("Match entries modified before DATE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("changed_since")
+                .long("changed-since-file")
+                .value_name("PATH")
+                .help("Match entries modified after PATH was")
+                .takes_value(true)
+                .allow_invalid_utf8(true)
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .help("Walk directories with N threads")
+                .takes_value(true)
+                .default_value("1")
+        )
+        .arg(
+            Arg::new("follow")
+                .short('L')
+                .help("Follow all symlinks")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("follow_roots")
+                .short('H')
+                .help("Follow symlinks given as start paths only")
+                .takes_value(false)
+                .overrides_with("follow")
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("KEY")
+                .help("Sort by name (the default), or largest/newest first with --sort=size/mtime")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true)
+                .default_missing_value("name")
+                .possible_values(["name", "size", "mtime"])
+        )
+        .arg(
+            Arg::new("reverse")
+                .long("reverse")
+                .help("Reverse the order of --sort")
+                .takes_value(false)
+                .requires("sort")
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .help("Print at most N entries")
+                .takes_value(true)
+                .conflicts_with("duplicates")
+        )
+        .arg(
+            Arg::new("perm")
+                .long("perm")
+                .value_name("MODE")
+                .help("Match mode bits exactly, all of -MODE or any of /MODE")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+        )
+        .arg(
+            Arg::new("user")
+                .long("user")
+                .value_name("USER")
+                .help("Match entries owned by user name or uid")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("group")
+                .long("group")
+                .value_name("GROUP")
+                .help("Match entries owned by group name or gid")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("nouser")
+                .long("nouser")
+                .help("Match entries whose uid has no user")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("nogroup")
+                .long("nogroup")
+                .help("Match entries whose gid has no group")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("gitignore")
+                .long("gitignore")
+                .help("Skip entries ignored by .gitignore, .ignore or global excludes")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("no_hidden")
+                .long("no-hidden")
+                .help("Skip entries whose name starts with a dot")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("duplicates")
+                .long("duplicates")
+                .help("Print groups of matching files with identical contents")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print one JSON object per entry")
+                .takes_value(false)
+                .conflicts_with_all(&["duplicates", "ls"])
+        )
+        .arg(
+            Arg::new("ls")
+                .long("ls")
+                .help("Print entries in the format of ls -dils")
+                .takes_value(false)
+                .conflicts_with("duplicates")
+        )
+        .arg(
+            Arg::new("tree")
+                .long("tree")
+                .help("Print entries as an indented tree")
+                .takes_value(false)
+                .conflicts_with_all(&["duplicates", "json", "ls", "limit"])
+        )
+        .arg(
+            Arg::new("du")
+                .long("du")
+                .help("Show sizes in --tree, with totals and file counts for directories")
+                .takes_value(false)
+                .requires("tree")
+        )
+        .arg(
+            Arg::new("pick")
+                .long("pick")
+                .help("Choose among the matches interactively and print the chosen paths")
+                .takes_value(false)
+                .conflicts_with_all(&["duplicates", "json", "ls", "tree", "limit"])
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("After the walk, keep printing matching entries as they are created or changed")
+                .takes_value(false)
+                .conflicts_with_all(&["duplicates", "tree", "pick", "limit"])
+        )
+        .arg(
+            Arg::new("quiet_errors")
+                .long("quiet-errors")
+                .help("Don't print errors for entries that can't be read; the exit code still tells")
+                .takes_value(false)
+        )
         .get_matches();
 
     let dirs = matches.values_of_lossy("dirs").unwrap();
@@ -67,34 +335,259 @@ pub fn get_args() -> RetType<Config> {
         }
     }
 
-    let entry_types = matches.values_of_lossy("types").map(|vals| {
-        vals.iter()
-            .filter_map(|val| match val.as_str() {
-                "d" => Some(EntryType::Dir),
-                "f" => Some(EntryType::File),
-                "l" => Some(EntryType::Link),
-                _ => None,
-            })
-            .collect()
-    });
+    let contains = match matches.value_of("contains") {
+        Some(val) => match bytes::Regex::new(val) {
+            Ok(re) => Some(re),
+            _ => return Err(From::from(format!("Invalid --contains \"{}\"", val))),
+        },
+        None => None,
+    };
+
+    let entry_types = matches.values_of_lossy("types").map(parse_types);
+    let xtypes = if matches.is_present("broken") {
+        Some(vec![EntryType::Link])
+    } else {
+        matches.values_of_lossy("xtypes").map(parse_types)
+    };
+
+    let now = SystemTime::now();
+    let mut newer = match matches.value_of("newer_than") {
+        Some(val) => match times::parse_time(val, now) {
+            Some(time) => Some(time),
+            None => return Err(From::from(format!("Invalid --newer-than \"{}\"", val))),
+        },
+        None => None,
+    };
+    let older = match matches.value_of("older_than") {
+        Some(val) => match times::parse_time(val, now) {
+            Some(time) => Some(time),
+            None => return Err(From::from(format!("Invalid --older-than \"{}\"", val))),
+        },
+        None => None,
+    };
+    if let Some(path) = matches.value_of_os("changed_since") {
+        let stamp = fs::metadata(path).and_then(|meta| meta.modified()).map_err(|e| {
+            format!("Invalid --changed-since-file \"{}\": {}", path.to_string_lossy(), e)
+        })?;
+        newer = newer.max(Some(stamp));
+    }
+
+    let jobs = matches.value_of("jobs").unwrap();
+    let jobs = match jobs.parse() {
+        Ok(n) if n > 0 => n,
+        _ => return Err(From::from(format!("Invalid --jobs \"{}\"", jobs))),
+    };
+
+    let perm = match matches.value_of("perm") {
+        Some(val) => match Perm::parse(val) {
+            Some(perm) => Some(perm),
+            None => return Err(From::from(format!("Invalid --perm \"{}\"", val))),
+        },
+        None => None,
+    };
+
+    let uid = match matches.value_of("user") {
+        Some(val) => match perm::user_id(val) {
+            Some(uid) => Some(uid),
+            None => return Err(From::from(format!("Invalid --user \"{}\"", val))),
+        },
+        None => None,
+    };
+
+    let gid = match matches.value_of("group") {
+        Some(val) => match perm::group_id(val) {
+            Some(gid) => Some(gid),
+            None => return Err(From::from(format!("Invalid --group \"{}\"", val))),
+        },
+        None => None,
+    };
+
+    let limit = match matches.value_of("limit") {
+        Some(val) => match val.parse() {
+            Ok(n) if n > 0 => Some(n),
+            _ => return Err(From::from(format!("Invalid --limit \"{}\"", val))),
+        },
+        None => None,
+    };
 
     Ok(Config {
         dirs,
         names: if names.is_empty() { None } else { Some(names) },
-        entry_types
+        entry_types,
+        xtypes,
+        empty: matches.is_present("empty"),
+        newer,
+        older,
+        jobs,
+        follow: if matches.is_present("follow") {
+            Follow::Always
+        } else if matches.is_present("follow_roots") {
+            Follow::Roots
+        } else {
+            Follow::Never
+        },
+        sort: matches.value_of("sort").map(|key| match key {
+            "size" => SortKey::Size,
+            "mtime" => SortKey::Mtime,
+            _ => SortKey::Name,
+        }),
+        reverse: matches.is_present("reverse"),
+        limit,
+        perm,
+        owner: Owner::new(
+            uid,
+            gid,
+            matches.is_present("nouser"),
+            matches.is_present("nogroup"),
+        ),
+        gitignore: matches.is_present("gitignore"),
+        hidden: !matches.is_present("no_hidden"),
+        duplicates: matches.is_present("duplicates"),
+        format: if matches.is_present("json") {
+            Format::Json
+        } else if matches.is_present("ls") {
+            Format::Ls
+        } else {
+            Format::Path
+        },
+        tree: matches.is_present("tree"),
+        du: matches.is_present("du"),
+        pick: matches.is_present("pick"),
+        watch: matches.is_present("watch"),
+        contains,
+        quiet_errors: matches.is_present("quiet_errors"),
     })
 }
 
-pub fn run(config: Config) -> RetType<()> {
+fn parse_types(vals: Vec<String>) -> Vec<EntryType> {
+    vals.iter()
+        .filter_map(|val| match val.as_str() {
+            "d" => Some(EntryType::Dir),
+            "f" => Some(EntryType::File),
+            "l" => Some(EntryType::Link),
+            "b" => Some(EntryType::Block),
+            "c" => Some(EntryType::Char),
+            "p" => Some(EntryType::Fifo),
+            "s" => Some(EntryType::Socket),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_type(file_type: fs::FileType, entry_type: &EntryType) -> bool {
+    match entry_type {
+        EntryType::Dir => file_type.is_dir(),
+        EntryType::File => file_type.is_file(),
+        EntryType::Link => file_type.is_symlink(),
+        _ => is_special(file_type, entry_type),
+    }
+}
+
+fn is_empty(entry: &DirEntry) -> bool {
+    if entry.file_type().is_dir() {
+        fs::read_dir(entry.path()).is_ok_and(|mut children| children.next().is_none())
+    } else {
+        entry.file_type().is_file() && entry.metadata().is_ok_and(|meta| meta.len() == 0)
+    }
+}
+
+// Lines longer than this are matched in pieces, so that a file without
+// newlines, such as a binary, isn't read into memory whole.
+const MAX_PIECE: usize = 64 * 1024;
+// How much of a piece is kept for the next one, so that a match across the
+// cut is still found as long as it is shorter than this.
+const OVERLAP: usize = 4 * 1024;
+
+// Reads one line at a time, so only files without a match are read to the end.
+fn has_line_matching(path: &Path, re: &bytes::Regex, errors: &Errors) -> bool {
+    let mut file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            errors.report(EntryError::new(path, e));
+            return false;
+        }
+    };
+
+    let mut line = vec![];
+    // Where the search starts in `line`: past the byte kept before the
+    // overlap, so that `^` matches only at the start of a line.
+    let mut from = 0;
+    loop {
+        let limit = (MAX_PIECE - line.len()) as u64;
+        match (&mut file).take(limit).read_until(b'\n', &mut line) {
+            Ok(_) if line.len() == MAX_PIECE && !line.ends_with(b"\n") => {
+                // A match running to the end of the piece may need more of the
+                // line, or may be a `$` that isn't the end of one; the next
+                // piece sees it again.
+                if re.find_at(&line, from).is_some_and(|m| m.end() < line.len()) {
+                    return true;
+                }
+                line.drain(..MAX_PIECE - OVERLAP - 1);
+                from = 1;
+            }
+            Ok(0) if line.len() <= from => return false,
+            // Without the line ending, so that `$` matches the end of a line.
+            Ok(_) if re.is_match_at(trim_line_ending(&line), from) => return true,
+            Ok(0) => return false,
+            Ok(_) => {
+                line.clear();
+                from = 0;
+            }
+            Err(e) => {
+                errors.report(EntryError::new(path, e));
+                return false;
+            }
+        }
+    }
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(unix)]
+fn is_special(file_type: fs::FileType, entry_type: &EntryType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    match entry_type {
+        EntryType::Block => file_type.is_block_device(),
+        EntryType::Char => file_type.is_char_device(),
+        EntryType::Fifo => file_type.is_fifo(),
+        EntryType::Socket => file_type.is_socket(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_special(_: fs::FileType, _: &EntryType) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn mode_owner(meta: &fs::Metadata) -> Option<(u32, u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((meta.mode(), meta.uid(), meta.gid()))
+}
+
+#[cfg(not(unix))]
+fn mode_owner(_: &fs::Metadata) -> Option<(u32, u32, u32)> {
+    None
+}
+
+/// Prints what matches `config` and returns whether every entry could be
+/// read.
+pub fn run(config: Config) -> RetType<bool> {
+    if config.pick && !pick::has_terminal() {
+        return Err(From::from("--pick needs a terminal"));
+    }
+    let errors = Errors::new(config.quiet_errors);
+
     let type_filter = |entry: &DirEntry| {
         match &config.entry_types {
-            Some(types) => types.iter().any(|t| {
-                    match t {
-                        EntryType::Link => entry.path_is_symlink(),
-                        EntryType::Dir => entry.file_type().is_dir(),
-                        EntryType::File => entry.file_type().is_file(),
-                    }
-                }),
+            // With links followed, only dangling ones are still links.
+            Some(types) => types.iter().any(|t| is_type(entry.file_type(), t)),
             None => true,
         }
     };
@@ -106,45 +599,307 @@ pub fn run(config: Config) -> RetType<()> {
         _ => true
     };
 
-    for dirname in &config.dirs {
-        let path = Path::new(&dirname);
+    let meta_filter = |entry: &DirEntry| {
+        if config.perm.is_none() && !config.owner.is_active() {
+            return true;
+        }
+        match entry.metadata().ok().and_then(|meta| mode_owner(&meta)) {
+            Some((mode, uid, gid)) => {
+                config.perm.as_ref().is_none_or(|perm| perm.is_match(mode))
+                    && config.owner.is_match(uid, gid)
+            }
+            None => false,
+        }
+    };
 
-        match fs::read_dir(&path) {
-            Err(e) => {
-                if path.is_file() {
-                    let print_path = match &config.entry_types {
-                        Some(types) => {
-                            types.iter().any(|t| {
-                                t == &EntryType::File
-                            })
-                        },
-                        None => true
-                    };
-
-                    if print_path {
-                        println!("{}", dirname);
+    // A symlink whose target can't be read counts as a link, as with find.
+    let xtype_filter = |entry: &DirEntry| match &config.xtypes {
+        Some(types) => {
+            let file_type = if entry.path_is_symlink() {
+                fs::metadata(entry.path()).map(|meta| meta.file_type()).ok()
+            } else {
+                Some(entry.file_type())
+            };
+            types.iter().any(|t| match file_type {
+                Some(file_type) => is_type(file_type, t),
+                None => t == &EntryType::Link,
+            })
+        }
+        None => true,
+    };
+
+    let time_filter = |entry: &DirEntry| {
+        if config.newer.is_none() && config.older.is_none() {
+            return true;
+        }
+        match entry.metadata().ok().and_then(|meta| meta.modified().ok()) {
+            Some(modified) => {
+                config.newer.is_none_or(|newer| modified > newer)
+                    && config.older.is_none_or(|older| modified < older)
+            }
+            None => false,
+        }
+    };
+
+    let keep = |entry: &DirEntry| {
+        type_filter(entry)
+            && name_filter(entry)
+            && meta_filter(entry)
+            && time_filter(entry)
+            && (!config.empty || is_empty(entry))
+            && xtype_filter(entry)
+            && config.contains.as_ref().is_none_or(|re| {
+                entry.file_type().is_file() && has_line_matching(entry.path(), re, &errors)
+            })
+    };
+
+    let stdout = io::stdout();
+    let mut printer = Printer {
+        config: &config,
+        out: stdout.lock(),
+        listing: Listing::default(),
+        errors: &errors,
+        printed: 0,
+    };
+
+    // Sorting by metadata, --duplicates, --tree and --pick need every entry
+    // before printing.
+    let hold_all = config.duplicates
+        || config.tree
+        || config.pick
+        || matches!(config.sort, Some(SortKey::Size | SortKey::Mtime));
+    let mut held = vec![];
+    let mut emit = |entry: &DirEntry, depth: usize| -> RetType<bool> {
+        if hold_all {
+            held.push((entry.clone(), depth));
+            Ok(true)
+        } else {
+            printer.print(entry, depth)
+        }
+    };
+
+    // Watching starts before the walk, so that what is made while walking
+    // is shown too, even if that means showing it twice.
+    let watching = if config.watch {
+        Some(watch::Watch::start(&config.dirs, &errors)?)
+    } else {
+        None
+    };
+
+    'dirs: for dirname in &config.dirs {
+        let ignores = config.gitignore.then(|| Ignores::new(Path::new(dirname)));
+        let prune = |entry: &DirEntry| is_pruned(&config, ignores.as_ref(), entry);
+
+        // Start paths go through the same filters as what is below them,
+        // whether they are files, directories or links.
+        if config.jobs > 1 {
+            // Sorting by name needs the whole tree, so only then are
+            // entries held back.
+            let by_name = config.sort == Some(SortKey::Name);
+            let mut entries = vec![];
+            let mut more = true;
+            walk::walk_parallel(dirname, config.jobs, config.follow, &prune, &keep, |result| {
+                match result {
+                    Ok(found) if by_name => entries.push(found),
+                    Ok(found) => more = emit(&found.entry, found.depth)?,
+                    Err(err) => errors.report(err),
+                }
+                Ok(more)
+            })?;
+
+            entries.sort_by(|a, b| {
+                cmp_names(a.entry.path(), b.entry.path(), config.reverse)
+            });
+            for found in entries {
+                if !more {
+                    break;
+                }
+                more = emit(&found.entry, found.depth)?;
+            }
+            if !more {
+                break 'dirs;
+            }
+        } else {
+            let mut walker = WalkDir::new(dirname)
+                .follow_links(config.follow == Follow::Always)
+                .follow_root_links(config.follow != Follow::Never);
+            if config.sort == Some(SortKey::Name) {
+                walker = if config.reverse {
+                    walker.sort_by(|a, b| b.file_name().cmp(a.file_name()))
+                } else {
+                    walker.sort_by_file_name()
+                };
+            }
+
+            let walker = walker
+                .into_iter()
+                .filter_entry(|entry| entry.depth() == 0 || !prune(entry));
+            for result in walker {
+                let (entry, depth) = match result {
+                    Ok(entry) if entry.depth() == 0 && config.follow == Follow::Roots => {
+                        (walk::resolve_root(entry), 0)
+                    }
+                    Ok(entry) => {
+                        let depth = entry.depth();
+                        (entry, depth)
                     }
-                    continue;
+                    Err(err) => match walk::dangling_link(&err) {
+                        Some(entry) if err.depth() == 0 || !prune(&entry) => {
+                            (entry, err.depth())
+                        }
+                        Some(_) => continue,
+                        None => {
+                            errors.report(err.into());
+                            continue;
+                        }
+                    },
+                };
+                if keep(&entry) && !emit(&entry, depth)? {
+                    break 'dirs;
                 }
-                eprintln!("{}: {}", dirname, e);
             }
-            _ => {
-                let entries = WalkDir::new(dirname)
-                    .into_iter()
-                    .filter_map(|e| match e {
-                        Err(err) => {
-                            eprintln!("{}", err);
-                            None
-                        },
-                        Ok(e) => Some(e)
-                    })
-                    .filter(type_filter)
-                    .filter(name_filter)
-                    .map(|entry| entry.path().display().to_string())
-                    .collect::<Vec<String>>();
-                println!("{}", entries.join("\n"));
+        }
+    }
+
+    if config.duplicates {
+        let paths = held.into_iter().map(|(entry, _)| entry.into_path()).collect();
+        dupes::print_duplicates(paths, &errors, &mut printer.out)?;
+        return Ok(!errors.any());
+    }
+
+    if config.tree {
+        let style = TreeStyle {
+            sort: config.sort.unwrap_or(SortKey::Name),
+            reverse: config.reverse,
+            du: config.du,
+        };
+        tree::print_tree(&config.dirs, held, &style, &mut printer.out)?;
+        return Ok(!errors.any());
+    }
+
+    // Names are already in order, reversed or not, from the walk.
+    if let Some(key) = config.sort.filter(|&key| hold_all && key != SortKey::Name) {
+        sort_by_meta(&mut held, key, config.reverse);
+    }
+
+    // Giving up without picking anything counts as a failure.
+    if config.pick {
+        let paths: Vec<_> = held
+            .iter()
+            .map(|(entry, _)| entry.path().display().to_string())
+            .collect();
+        let picked = match pick::pick(&paths)? {
+            Some(picked) => picked,
+            None => return Ok(false),
+        };
+        for i in picked {
+            writeln!(printer.out, "{}", paths[i])?;
+        }
+        return Ok(!errors.any());
+    }
+
+    for (entry, depth) in held {
+        if !printer.print(&entry, depth)? {
+            break;
+        }
+    }
+
+    if let Some(watching) = watching {
+        let ignores: Vec<_> = config
+            .dirs
+            .iter()
+            .map(|dirname| config.gitignore.then(|| Ignores::new(Path::new(dirname))))
+            .collect();
+        let prune = |root: usize, entry: &DirEntry| {
+            is_pruned(&config, ignores[root].as_ref(), entry)
+        };
+        let follow = config.follow == Follow::Always;
+        watching.run(&errors, |changes| {
+            let emit = |entry: &DirEntry, depth| printer.print(entry, depth);
+            watch::walk_changes(&config.dirs, changes, follow, &prune, &keep, &errors, emit)
+        })?;
+    }
+
+    Ok(!errors.any())
+}
+
+// Pruned entries are never descended into, unlike filtered ones.
+fn is_pruned(config: &Config, ignores: Option<&Ignores>, entry: &DirEntry) -> bool {
+    !config.hidden && entry.file_name().as_encoded_bytes().starts_with(b".")
+        || ignores.is_some_and(|ignores| {
+            ignores.is_ignored(entry.path(), entry.file_type().is_dir())
+        })
+}
+
+// Prints matching entries in the chosen format, stopping at --limit.
+struct Printer<'a, W> {
+    config: &'a Config,
+    out: W,
+    listing: Listing,
+    errors: &'a Errors,
+    printed: usize,
+}
+
+impl<W: Write> Printer<'_, W> {
+    // Returns whether more entries may be printed.
+    fn print(&mut self, entry: &DirEntry, depth: usize) -> RetType<bool> {
+        let line = match self.config.format {
+            Format::Path => Ok(entry.path().display().to_string()),
+            Format::Json => self.listing.json(entry, depth),
+            Format::Ls => self.listing.ls(entry),
+        };
+        match line {
+            Ok(line) => {
+                writeln!(self.out, "{}", line)?;
+                self.printed += 1;
             }
+            Err(e) => self.errors.report(EntryError::new(entry.path(), e)),
+        }
+        Ok(self.config.limit.is_none_or(|limit| self.printed < limit))
+    }
+}
+
+// Orders paths the way a walk sorted by name visits them: parents first,
+// then siblings by name.
+fn cmp_names(a: &Path, b: &Path, reverse: bool) -> Ordering {
+    let (mut a, mut b) = (a.components(), b.components());
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) if x == y => {}
+            (Some(x), Some(y)) if reverse => return y.cmp(&x),
+            (Some(x), Some(y)) => return x.cmp(&y),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
         }
     }
-    Ok(())
+}
+
+// Largest or newest first, like ls -S and ls -t, with ties in path order.
+fn sort_by_meta(held: &mut Vec<(DirEntry, usize)>, key: SortKey, reverse: bool) {
+    let value = |entry: &DirEntry| -> u128 {
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(_) => return 0,
+        };
+        match key {
+            SortKey::Size => meta.len().into(),
+            SortKey::Mtime => meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos()),
+            SortKey::Name => 0,
+        }
+    };
+
+    let mut keyed: Vec<_> = held
+        .drain(..)
+        .map(|(entry, depth)| (value(&entry), entry, depth))
+        .collect();
+    keyed.sort_by(|(a, a_entry, _), (b, b_entry, _)| {
+        let order = if reverse { a.cmp(b) } else { b.cmp(a) };
+        order.then_with(|| a_entry.path().cmp(b_entry.path()))
+    });
+    held.extend(keyed.into_iter().map(|(_, entry, depth)| (entry, depth)));
 }
@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    fs,
+    time::{Duration, SystemTime},
+};
+use chrono::{DateTime, Local, Utc};
+use serde_json::json;
+use walkdir::DirEntry;
+use crate::RetType;
+
+// Files older than this get a year instead of a time in --ls, as with ls.
+const SIX_MONTHS: Duration = Duration::from_secs(182 * 24 * 60 * 60);
+
+// The inode fields that std only exposes on Unix.
+struct Stat {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    ino: u64,
+    nlink: u64,
+    blocks: u64,
+}
+
+#[cfg(unix)]
+fn stat(meta: &fs::Metadata) -> Stat {
+    use std::os::unix::fs::MetadataExt;
+
+    Stat {
+        mode: meta.mode(),
+        uid: meta.uid(),
+        gid: meta.gid(),
+        ino: meta.ino(),
+        nlink: meta.nlink(),
+        blocks: meta.blocks(),
+    }
+}
+
+#[cfg(not(unix))]
+fn stat(meta: &fs::Metadata) -> Stat {
+    let mode = match (meta.is_dir(), meta.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    };
+    Stat { mode, uid: 0, gid: 0, ino: 0, nlink: 1, blocks: (meta.len() + 511) / 512 }
+}
+
+/// Formats entries for --json and --ls, remembering user and group names.
+#[derive(Default)]
+pub struct Listing {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl Listing {
+    /// One JSON object on a single line.
+    pub fn json(&self, entry: &DirEntry, depth: usize) -> RetType<String> {
+        let meta = entry.metadata()?;
+        let stat = stat(&meta);
+        let mtime = DateTime::<Utc>::from(meta.modified()?).timestamp();
+
+        Ok(json!({
+            "path": entry.path().to_string_lossy(),
+            "type": type_char(&meta).to_string(),
+            "size": meta.len(),
+            "mode": format!("{:04o}", stat.mode & 0o7777),
+            "uid": stat.uid,
+            "gid": stat.gid,
+            "mtime": mtime,
+            "target": link_target(entry),
+            "depth": depth,
+        })
+        .to_string())
+    }
+
+    /// A line in the format of `find -ls`, which is that of `ls -dils`.
+    pub fn ls(&mut self, entry: &DirEntry) -> RetType<String> {
+        let meta = entry.metadata()?;
+        let stat = stat(&meta);
+        let modified = meta.modified()?;
+
+        let time = DateTime::<Local>::from(modified);
+        let recent = SystemTime::now()
+            .checked_sub(SIX_MONTHS)
+            .is_some_and(|cutoff| modified > cutoff);
+        let time = if recent {
+            time.format("%b %e %H:%M")
+        } else {
+            time.format("%b %e  %Y")
+        };
+
+        let user = self.users.entry(stat.uid).or_insert_with(|| user_name(stat.uid));
+        let group = self.groups.entry(stat.gid).or_insert_with(|| group_name(stat.gid));
+        let mut line = format!(
+            "{:>9} {:>6} {} {:>3} {:<8} {:<8} {:>8} {} {}",
+            stat.ino,
+            stat.blocks / 2,
+            mode_string(&meta, stat.mode),
+            stat.nlink,
+            user,
+            group,
+            meta.len(),
+            time,
+            entry.path().display(),
+        );
+        if let Some(target) = link_target(entry) {
+            line.push_str(" -> ");
+            line.push_str(&target);
+        }
+        Ok(line)
+    }
+}
+
+fn link_target(entry: &DirEntry) -> Option<String> {
+    if !entry.path_is_symlink() {
+        return None;
+    }
+    fs::read_link(entry.path())
+        .ok()
+        .map(|target| target.to_string_lossy().into_owned())
+}
+
+// The letters of `find -type`.
+fn type_char(meta: &fs::Metadata) -> char {
+    let file_type = meta.file_type();
+    if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_file() {
+        'f'
+    } else {
+        special_char(file_type)
+    }
+}
+
+#[cfg(unix)]
+fn special_char(file_type: fs::FileType) -> char {
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else {
+        '?'
+    }
+}
+
+#[cfg(not(unix))]
+fn special_char(_: fs::FileType) -> char {
+    '?'
+}
+
+// Such as `-rwsr-xr-x` or `drwxrwxrwt`.
+fn mode_string(meta: &fs::Metadata, mode: u32) -> String {
+    let mut chars = vec![match type_char(meta) {
+        'f' => '-',
+        c => c,
+    }];
+
+    for (shift, special, set, unset) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+        let bits = (mode >> shift) & 0o7;
+        chars.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        chars.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        chars.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    chars.into_iter().collect()
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> String {
+    uzers::get_user_by_uid(uid)
+        .map_or_else(|| uid.to_string(), |user| user.name().to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> String {
+    uzers::get_group_by_gid(gid)
+        .map_or_else(|| gid.to_string(), |group| group.name().to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn user_name(uid: u32) -> String {
+    uid.to_string()
+}
+
+#[cfg(not(unix))]
+fn group_name(gid: u32) -> String {
+    gid.to_string()
+}
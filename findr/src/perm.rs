@@ -0,0 +1,178 @@
+use std::{collections::HashMap, sync::Mutex};
+
+/// How `--perm` compares the mode of an entry with MODE.
+#[derive(Debug, PartialEq)]
+enum PermKind {
+    /// `MODE`: exactly these bits are set.
+    Exact,
+    /// `-MODE`: all of these bits are set.
+    All,
+    /// `/MODE`: any of these bits is set.
+    Any,
+}
+
+#[derive(Debug)]
+pub struct Perm {
+    bits: u32,
+    kind: PermKind,
+}
+
+impl Perm {
+    /// Parses an octal (`4000`) or symbolic (`u+s`, `go+w,o+r`) mode,
+    /// optionally prefixed with `-` or `/`.
+    pub fn parse(val: &str) -> Option<Perm> {
+        let (kind, mode) = if let Some(mode) = val.strip_prefix('-') {
+            (PermKind::All, mode)
+        } else if let Some(mode) = val.strip_prefix('/') {
+            (PermKind::Any, mode)
+        } else {
+            (PermKind::Exact, val)
+        };
+
+        let bits = if !mode.is_empty() && mode.bytes().all(|b| (b'0'..=b'7').contains(&b)) {
+            u32::from_str_radix(mode, 8).ok().filter(|bits| *bits <= 0o7777)?
+        } else {
+            parse_symbolic(mode)?
+        };
+
+        Some(Perm { bits, kind })
+    }
+
+    pub fn is_match(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self.kind {
+            PermKind::Exact => mode == self.bits,
+            PermKind::All => mode & self.bits == self.bits,
+            // Like find, `/000` matches everything.
+            PermKind::Any => self.bits == 0 || mode & self.bits != 0,
+        }
+    }
+}
+
+// Symbolic modes are applied to an empty mode, as find does.
+fn parse_symbolic(mode: &str) -> Option<u32> {
+    let mut bits = 0;
+    for clause in mode.split(',') {
+        let op_at = clause.find(|c| !"ugoa".contains(c))?;
+        let (who, rest) = clause.split_at(op_at);
+        let mut chars = rest.chars();
+        let op = chars.next()?;
+
+        let mut mask = 0;
+        for c in who.chars() {
+            mask |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o0007,
+                _ => 0o6777,
+            };
+        }
+        if who.is_empty() {
+            mask = 0o6777;
+        }
+
+        let mut clause_bits = 0;
+        for c in chars {
+            clause_bits |= match c {
+                'r' => 0o444 & mask,
+                'w' => 0o222 & mask,
+                'x' => 0o111 & mask,
+                's' => 0o6000 & mask,
+                't' => 0o1000,
+                _ => return None,
+            };
+        }
+
+        match op {
+            '+' | '=' => bits |= clause_bits,
+            '-' => bits &= !clause_bits,
+            _ => return None,
+        }
+    }
+    Some(bits)
+}
+
+// Remembers whether an id exists in the user or group database, so each
+// one is only looked up once per run.
+#[derive(Debug, Default)]
+struct KnownIds(Mutex<HashMap<u32, bool>>);
+
+impl KnownIds {
+    fn contains(&self, id: u32, lookup: fn(u32) -> bool) -> bool {
+        *self.0.lock().unwrap().entry(id).or_insert_with(|| lookup(id))
+    }
+}
+
+/// The `--user`, `--group`, `--nouser` and `--nogroup` tests.
+#[derive(Debug, Default)]
+pub struct Owner {
+    uid: Option<u32>,
+    gid: Option<u32>,
+    nouser: bool,
+    nogroup: bool,
+    users: KnownIds,
+    groups: KnownIds,
+}
+
+impl Owner {
+    pub fn new(uid: Option<u32>, gid: Option<u32>, nouser: bool, nogroup: bool) -> Owner {
+        Owner { uid, gid, nouser, nogroup, ..Default::default() }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.uid.is_some() || self.gid.is_some() || self.nouser || self.nogroup
+    }
+
+    pub fn is_match(&self, uid: u32, gid: u32) -> bool {
+        self.uid.is_none_or(|want| want == uid)
+            && self.gid.is_none_or(|want| want == gid)
+            && !(self.nouser && self.users.contains(uid, user_exists))
+            && !(self.nogroup && self.groups.contains(gid, group_exists))
+    }
+}
+
+/// Resolves a user name, or failing that a numeric uid.
+#[cfg(unix)]
+pub fn user_id(val: &str) -> Option<u32> {
+    uzers::get_user_by_name(val)
+        .map(|user| user.uid())
+        .or_else(|| val.parse().ok())
+}
+
+/// Resolves a group name, or failing that a numeric gid.
+#[cfg(unix)]
+pub fn group_id(val: &str) -> Option<u32> {
+    uzers::get_group_by_name(val)
+        .map(|group| group.gid())
+        .or_else(|| val.parse().ok())
+}
+
+#[cfg(unix)]
+fn user_exists(uid: u32) -> bool {
+    uzers::get_user_by_uid(uid).is_some()
+}
+
+#[cfg(unix)]
+fn group_exists(gid: u32) -> bool {
+    uzers::get_group_by_gid(gid).is_some()
+}
+
+#[cfg(not(unix))]
+pub fn user_id(val: &str) -> Option<u32> {
+    val.parse().ok()
+}
+
+#[cfg(not(unix))]
+pub fn group_id(val: &str) -> Option<u32> {
+    val.parse().ok()
+}
+
+#[cfg(not(unix))]
+fn user_exists(_: u32) -> bool {
+    true
+}
+
+#[cfg(not(unix))]
+fn group_exists(_: u32) -> bool {
+    true
+}
@@ -0,0 +1,338 @@
+use std::{
+    cmp::Reverse,
+    io::{self, IsTerminal, Stderr, Write},
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use crate::RetType;
+
+// Lines above the list: the query and the match count.
+const HEADER: u16 = 2;
+
+/// Whether --pick can show its list and read keys.
+pub fn has_terminal() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Shows `choices` on stderr, so that only what is picked goes to stdout,
+/// and lets the user narrow them down by typing. Enter picks the one under
+/// the cursor, or those marked with Tab. Returns the indices of the picked
+/// choices in the order they were listed, or None if the user gave up.
+pub fn pick(choices: &[String]) -> RetType<Option<Vec<usize>>> {
+    let mut screen = Screen::open()?;
+    let mut picker = Picker::new(choices);
+    loop {
+        picker.draw(&mut screen.out)?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+        match picker.handle(key) {
+            Action::Continue => {}
+            Action::Cancel => return Ok(None),
+            Action::Accept => return Ok(Some(picker.picked())),
+        }
+    }
+}
+
+// Raw mode on the alternate screen, undone when dropped so that the
+// terminal is left usable on errors too.
+struct Screen {
+    out: Stderr,
+}
+
+impl Screen {
+    fn open() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stderr();
+        if let Err(e) = execute!(out, terminal::EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Screen { out })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+enum Action {
+    Continue,
+    Cancel,
+    Accept,
+}
+
+struct Picker<'a> {
+    choices: &'a [String],
+    matcher: SkimMatcherV2,
+    query: String,
+    // Indices of the choices matching the query, best first.
+    shown: Vec<usize>,
+    marked: Vec<bool>,
+    // Positions in `shown` of the selected line and of the top line.
+    cursor: usize,
+    offset: usize,
+    rows: usize,
+}
+
+impl<'a> Picker<'a> {
+    fn new(choices: &'a [String]) -> Picker<'a> {
+        Picker {
+            choices,
+            matcher: SkimMatcherV2::default(),
+            query: String::new(),
+            shown: (0..choices.len()).collect(),
+            marked: vec![false; choices.len()],
+            cursor: 0,
+            offset: 0,
+            rows: 0,
+        }
+    }
+
+    // Without a query, choices keep their order. Otherwise the best matches
+    // come first, and equally good ones in their order.
+    fn filter(&mut self) {
+        if self.query.is_empty() {
+            self.shown = (0..self.choices.len()).collect();
+        } else {
+            let mut scored: Vec<_> = self
+                .choices
+                .iter()
+                .enumerate()
+                .filter_map(|(i, choice)| {
+                    self.matcher.fuzzy_match(choice, &self.query).map(|score| (score, i))
+                })
+                .collect();
+            scored.sort_by_key(|&(score, i)| (Reverse(score), i));
+            self.shown = scored.into_iter().map(|(_, i)| i).collect();
+        }
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    fn move_cursor(&mut self, by: isize) {
+        let last = self.shown.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(by).min(last);
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.rows > 0 && self.cursor >= self.offset + self.rows {
+            self.offset = self.cursor + 1 - self.rows;
+        }
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = self.rows.max(1) as isize;
+        match key.code {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c' | 'g') if ctrl => return Action::Cancel,
+            KeyCode::Enter if !self.shown.is_empty() => return Action::Accept,
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::Tab | KeyCode::BackTab => {
+                if let Some(&i) = self.shown.get(self.cursor) {
+                    self.marked[i] = !self.marked[i];
+                }
+                self.move_cursor(if key.code == KeyCode::Tab { 1 } else { -1 });
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.filter();
+            }
+            KeyCode::Backspace if self.query.pop().is_some() => self.filter(),
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter();
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn picked(&self) -> Vec<usize> {
+        let marked: Vec<_> = (0..self.choices.len()).filter(|&i| self.marked[i]).collect();
+        if marked.is_empty() {
+            self.shown.get(self.cursor).copied().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        self.rows = height.saturating_sub(HEADER) as usize;
+        self.move_cursor(0);
+
+        let width = width as usize;
+        let marked = self.marked.iter().filter(|&&marked| marked).count();
+        let mut count = format!("  {}/{}", self.shown.len(), self.choices.len());
+        if marked > 0 {
+            count.push_str(&format!(" ({} marked)", marked));
+        }
+
+        queue!(
+            out,
+            cursor::Hide,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0),
+            Print(clip(&format!("> {}", self.query), width)),
+            cursor::MoveTo(0, 1),
+            Print(clip(&count, width)),
+        )?;
+        for row in 0..self.rows {
+            let pos = self.offset + row;
+            let i = match self.shown.get(pos) {
+                Some(&i) => i,
+                None => break,
+            };
+            let line = format!(
+                "{}{}{}",
+                if pos == self.cursor { '>' } else { ' ' },
+                if self.marked[i] { '*' } else { ' ' },
+                self.choices[i]
+            );
+            queue!(out, cursor::MoveTo(0, HEADER + row as u16))?;
+            if pos == self.cursor {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(clip(&line, width)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(clip(&line, width)))?;
+            }
+        }
+
+        let column = 2 + self.query.chars().count();
+        queue!(
+            out,
+            cursor::MoveTo(column.min(width.saturating_sub(1)) as u16, 0),
+            cursor::Show
+        )?;
+        out.flush()
+    }
+}
+
+fn clip(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choices(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn press(picker: &mut Picker, code: KeyCode) -> Action {
+        picker.handle(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_query(picker: &mut Picker, query: &str) {
+        for c in query.chars() {
+            press(picker, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn fuzzy_order() {
+        let choices = choices(&["src/main.rs", "b/x", "src/lib.rs", "a/x", "xyz"]);
+        let mut picker = Picker::new(&choices);
+        assert_eq!(picker.shown, [0, 1, 2, 3, 4]);
+
+        type_query(&mut picker, "lib");
+        assert_eq!(picker.shown, [2]);
+
+        // Equally good matches keep their order.
+        picker.handle(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        type_query(&mut picker, "/x");
+        assert_eq!(picker.shown, [1, 3]);
+
+        press(&mut picker, KeyCode::Backspace);
+        press(&mut picker, KeyCode::Backspace);
+        assert_eq!(picker.shown, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn marking() {
+        let choices = choices(&["a", "b", "c", "d"]);
+        let mut picker = Picker::new(&choices);
+        assert_eq!(picker.picked(), [0]);
+
+        press(&mut picker, KeyCode::Down);
+        assert_eq!(picker.picked(), [1]);
+
+        press(&mut picker, KeyCode::Tab);
+        press(&mut picker, KeyCode::Tab);
+        press(&mut picker, KeyCode::Tab);
+        assert_eq!(picker.picked(), [1, 2, 3]);
+
+        // BackTab unmarks going up, and the cursor stops at the ends.
+        press(&mut picker, KeyCode::BackTab);
+        press(&mut picker, KeyCode::BackTab);
+        assert_eq!(picker.picked(), [1]);
+        assert_eq!(picker.cursor, 1);
+        press(&mut picker, KeyCode::BackTab);
+        press(&mut picker, KeyCode::BackTab);
+        assert_eq!(picker.picked(), [0]);
+        assert_eq!(picker.cursor, 0);
+
+        // Marks outlast the query that hid them.
+        type_query(&mut picker, "d");
+        assert_eq!(picker.shown, [3]);
+        assert_eq!(picker.picked(), [0]);
+    }
+
+    #[test]
+    fn paging() {
+        let names: Vec<_> = (0..10).map(|i| i.to_string()).collect();
+        let mut picker = Picker::new(&names);
+        picker.rows = 4;
+
+        press(&mut picker, KeyCode::PageDown);
+        assert_eq!((picker.cursor, picker.offset), (4, 1));
+        press(&mut picker, KeyCode::PageDown);
+        press(&mut picker, KeyCode::PageDown);
+        assert_eq!((picker.cursor, picker.offset), (9, 6));
+        press(&mut picker, KeyCode::PageUp);
+        assert_eq!((picker.cursor, picker.offset), (5, 5));
+        press(&mut picker, KeyCode::PageUp);
+        press(&mut picker, KeyCode::PageUp);
+        assert_eq!((picker.cursor, picker.offset), (0, 0));
+        assert_eq!(picker.picked(), [0]);
+    }
+
+    #[test]
+    fn accept_and_cancel() {
+        let choices = choices(&["a", "b"]);
+        let mut picker = Picker::new(&choices);
+
+        // Nothing to pick when nothing matches.
+        type_query(&mut picker, "zz");
+        assert!(picker.shown.is_empty());
+        assert!(picker.picked().is_empty());
+        assert!(matches!(press(&mut picker, KeyCode::Enter), Action::Continue));
+
+        picker.handle(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert!(matches!(press(&mut picker, KeyCode::Enter), Action::Accept));
+        assert!(matches!(press(&mut picker, KeyCode::Esc), Action::Cancel));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(matches!(picker.handle(ctrl_c), Action::Cancel));
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+// Local times without a zone, tried in order after RFC 3339.
+const LOCAL_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parses the argument of --newer-than or --older-than: an ISO-8601 date or
+/// time, such as `2024-05-01` or `2024-05-01T12:00:00+02:00`, or a time ago
+/// such as `90m`, `2h` or `1d12h`. Times without a zone are local.
+pub fn parse_time(val: &str, now: SystemTime) -> Option<SystemTime> {
+    if let Some(ago) = parse_duration(val) {
+        return now.checked_sub(ago);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(val) {
+        return Some(time.into());
+    }
+
+    let naive = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(val, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(val, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local.from_local_datetime(&naive).earliest().map(SystemTime::from)
+}
+
+// One or more counts of s, m, h, d or w, as in `1d12h`.
+fn parse_duration(val: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut rest = val;
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let count: u64 = rest[..digits].parse().ok()?;
+        let seconds = match rest[digits..].chars().next()? {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        total = total.checked_add(count.checked_mul(seconds)?)?;
+        rest = &rest[digits + 1..];
+    }
+    (!val.is_empty()).then(|| Duration::from_secs(total))
+}
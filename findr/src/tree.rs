@@ -0,0 +1,206 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use walkdir::DirEntry;
+use crate::{RetType, SortKey};
+
+// An entry in the tree, or a directory only shown to hold one. Once
+// `add_totals` has run, `size`, `files` and `mtime` of a directory cover
+// the matching files below it.
+#[derive(Default)]
+struct Node {
+    is_dir: bool,
+    target: Option<String>,
+    size: u64,
+    files: usize,
+    mtime: u128,
+    children: BTreeMap<OsString, Node>,
+}
+
+impl Node {
+    fn dir() -> Node {
+        Node { is_dir: true, ..Node::default() }
+    }
+
+    fn set_entry(&mut self, entry: &DirEntry) {
+        self.is_dir = entry.file_type().is_dir();
+        if entry.path_is_symlink() {
+            self.target = fs::read_link(entry.path())
+                .ok()
+                .map(|target| target.to_string_lossy().into_owned());
+        }
+        if let Ok(meta) = entry.metadata() {
+            if !self.is_dir {
+                self.size = meta.len();
+            }
+            self.mtime = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos());
+        }
+        if !self.is_dir {
+            self.files = 1;
+        }
+    }
+
+    fn add_totals(&mut self) {
+        for child in self.children.values_mut() {
+            child.add_totals();
+            self.size += child.size;
+            self.files += child.files;
+            self.mtime = self.mtime.max(child.mtime);
+        }
+    }
+
+    fn count_dirs(&self) -> usize {
+        self.children
+            .values()
+            .filter(|child| child.is_dir)
+            .map(|child| 1 + child.count_dirs())
+            .sum()
+    }
+}
+
+/// How the children of each directory are ordered and what is shown.
+pub struct TreeStyle {
+    pub sort: SortKey,
+    pub reverse: bool,
+    pub du: bool,
+}
+
+/// Prints the entries found below each start path as an indented tree, like
+/// the `tree` command. Directories that did not match themselves are still
+/// shown when something below them did. `depth` says how many components
+/// of an entry's path lie below its start path.
+pub fn print_tree(
+    roots: &[String],
+    entries: Vec<(DirEntry, usize)>,
+    style: &TreeStyle,
+    out: &mut impl Write,
+) -> RetType<()> {
+    let mut trees: HashMap<PathBuf, Node> = HashMap::new();
+    for (entry, depth) in entries {
+        let components: Vec<Component> = entry.path().components().collect();
+        let (root, below) = components.split_at(components.len().saturating_sub(depth));
+
+        let mut node = trees
+            .entry(root.iter().collect())
+            .or_insert_with(Node::dir);
+        for name in below {
+            node = node
+                .children
+                .entry(name.as_os_str().to_os_string())
+                .or_insert_with(Node::dir);
+        }
+        node.set_entry(&entry);
+    }
+
+    let (mut dirs, mut files) = (0, 0);
+    for root in roots {
+        let mut tree = match trees.remove(Path::new(root)) {
+            Some(tree) => tree,
+            None => continue,
+        };
+        tree.add_totals();
+        dirs += tree.count_dirs();
+        files += tree.files;
+
+        writeln!(out, "{}", line(root, &tree, style))?;
+        print_children(&tree, "", style, out)?;
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} director{}, {} file{}",
+        dirs,
+        if dirs == 1 { "y" } else { "ies" },
+        files,
+        if files == 1 { "" } else { "s" },
+    )?;
+    Ok(())
+}
+
+fn print_children(
+    node: &Node,
+    prefix: &str,
+    style: &TreeStyle,
+    out: &mut impl Write,
+) -> RetType<()> {
+    // The map is in name order and stable sorts keep ties that way. Like
+    // --sort elsewhere, sizes and times go largest or newest first.
+    let value = |node: &Node| match style.sort {
+        SortKey::Size => node.size.into(),
+        SortKey::Mtime => node.mtime,
+        SortKey::Name => 0,
+    };
+    let mut children: Vec<_> = node.children.iter().collect();
+    match style.sort {
+        SortKey::Name if style.reverse => children.reverse(),
+        SortKey::Name => {}
+        _ if style.reverse => children.sort_by_key(|(_, child)| value(child)),
+        _ => children.sort_by_key(|(_, child)| Reverse(value(child))),
+    }
+
+    for (i, (name, child)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let name = name.to_string_lossy();
+        writeln!(
+            out,
+            "{}{}{}",
+            prefix,
+            if last { "└── " } else { "├── " },
+            line(&name, child, style)
+        )?;
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        print_children(child, &prefix, style, out)?;
+    }
+    Ok(())
+}
+
+fn line(name: &str, node: &Node, style: &TreeStyle) -> String {
+    let mut line = if style.du {
+        format!("[{:>4}]  {}", human_size(node.size), name)
+    } else {
+        name.to_string()
+    };
+    if let Some(target) = &node.target {
+        line.push_str(" -> ");
+        line.push_str(target);
+    }
+    if style.du && node.is_dir {
+        line.push_str(&format!(
+            " ({} file{})",
+            node.files,
+            if node.files == 1 { "" } else { "s" }
+        ));
+    }
+    line
+}
+
+// Such as 512, 4.0K or 12M, as with tree -h.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size < 10.0 {
+        format!("{:.1}{}", size, UNITS[unit])
+    } else {
+        format!("{:.0}{}", size, UNITS[unit])
+    }
+}
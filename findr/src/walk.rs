@@ -0,0 +1,286 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{mpsc::{self, Sender}, Arc, Condvar, Mutex},
+    thread,
+};
+use walkdir::{DirEntry, WalkDir};
+use crate::{errors::EntryError, RetType};
+
+/// Which symlinks are followed, like find's -P (the default), -H and -L.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Follow {
+    Never,
+    Roots,
+    Always,
+}
+
+/// An entry found by the parallel walker. `depth` counts from the start
+/// path, whereas `entry.depth()` is relative to the directory it was read
+/// from.
+pub struct Found {
+    pub entry: DirEntry,
+    pub depth: usize,
+}
+
+// What a directory is, regardless of the path it was reached by.
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = fs::metadata(path)?;
+    Ok((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    fs::canonicalize(path)
+}
+
+// A directory and those above it, kept while following links so that one
+// pointing back up can be caught.
+struct Ancestor {
+    id: DirId,
+    path: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+fn loop_ancestor<'a>(
+    mut ancestor: Option<&'a Arc<Ancestor>>,
+    id: &DirId,
+) -> Option<&'a Path> {
+    while let Some(dir) = ancestor {
+        if &dir.id == id {
+            return Some(&dir.path);
+        }
+        ancestor = dir.parent.as_ref();
+    }
+    None
+}
+
+struct Pending {
+    path: PathBuf,
+    depth: usize,
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+// Directories waiting to be read and the number of workers reading one.
+// The walk is over once both are empty.
+struct Queue {
+    dirs: Vec<Pending>,
+    busy: usize,
+}
+
+struct Shared<'a, P, K> {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    follow: Follow,
+    prune: &'a P,
+    keep: &'a K,
+}
+
+type Sent = Result<Found, EntryError>;
+
+/// With links followed, walkdir reports a symlink to nothing as an error.
+/// Like find -L, this gives back the link itself instead.
+pub fn dangling_link(err: &walkdir::Error) -> Option<DirEntry> {
+    let path = err.path()?;
+    let is_link = fs::symlink_metadata(path)
+        .is_ok_and(|meta| meta.file_type().is_symlink());
+    if !is_link || path.exists() {
+        return None;
+    }
+    WalkDir::new(path).follow_root_links(false).into_iter().next()?.ok()
+}
+
+/// For -H, walkdir descends into a symlinked start path but still reports
+/// it as a link. Like find, this looks at what it points to instead.
+pub fn resolve_root(entry: DirEntry) -> DirEntry {
+    if !entry.path_is_symlink() {
+        return entry;
+    }
+    match WalkDir::new(entry.path()).follow_links(true).into_iter().next() {
+        Some(Ok(target)) => target,
+        _ => entry,
+    }
+}
+
+/// Walks `root` with `jobs` threads, skipping entries below it (and whole
+/// directories) for which `prune` is true. Entries accepted by `keep` and
+/// errors are handed to `emit` on the calling thread as soon as they are
+/// found, in no particular order, until it returns false.
+pub fn walk_parallel<P, K, E>(
+    root: &str,
+    jobs: usize,
+    follow: Follow,
+    prune: &P,
+    keep: &K,
+    mut emit: E,
+) -> RetType<()>
+where
+    P: Fn(&DirEntry) -> bool + Sync,
+    K: Fn(&DirEntry) -> bool + Sync,
+    E: FnMut(Sent) -> RetType<bool>,
+{
+    let shared = Shared {
+        queue: Mutex::new(Queue { dirs: vec![], busy: 0 }),
+        ready: Condvar::new(),
+        follow,
+        prune,
+        keep,
+    };
+
+    let walker = WalkDir::new(root)
+        .max_depth(0)
+        .follow_links(follow != Follow::Never);
+    for result in walker {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => match dangling_link(&err) {
+                Some(entry) => entry,
+                None => {
+                    emit(Err(err.into()))?;
+                    return Ok(());
+                }
+            },
+        };
+
+        if entry.file_type().is_dir() {
+            let ancestors = match follow {
+                Follow::Always => dir_id(entry.path()).ok().map(|id| {
+                    Arc::new(Ancestor {
+                        id,
+                        path: entry.path().to_path_buf(),
+                        parent: None,
+                    })
+                }),
+                _ => None,
+            };
+            shared.queue.lock().unwrap().dirs.push(Pending {
+                path: entry.path().to_path_buf(),
+                depth: 0,
+                ancestors,
+            });
+        }
+        if keep(&entry) && !emit(Ok(Found { entry, depth: 0 }))? {
+            return Ok(());
+        }
+    }
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let shared = &shared;
+            scope.spawn(move || work(shared, tx));
+        }
+        drop(tx);
+
+        // Dropping `rx` early makes the workers' sends fail, which stops them.
+        for found in rx {
+            if !emit(found)? {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn next_dir<P, K>(shared: &Shared<P, K>) -> Option<Pending> {
+    let mut queue = shared.queue.lock().unwrap();
+    loop {
+        if let Some(dir) = queue.dirs.pop() {
+            queue.busy += 1;
+            return Some(dir);
+        }
+        if queue.busy == 0 {
+            return None;
+        }
+        queue = shared.ready.wait(queue).unwrap();
+    }
+}
+
+fn work<P, K>(shared: &Shared<P, K>, tx: Sender<Sent>)
+where
+    P: Fn(&DirEntry) -> bool,
+    K: Fn(&DirEntry) -> bool,
+{
+    while let Some(dir) = next_dir(shared) {
+        let hung_up = read_dir(shared, &dir, &tx).is_err();
+
+        let mut queue = shared.queue.lock().unwrap();
+        queue.busy -= 1;
+        if hung_up {
+            queue.dirs.clear();
+        }
+        shared.ready.notify_all();
+    }
+}
+
+// Sends what is in `dir` and queues its subdirectories. Fails once nobody
+// is listening any more.
+fn read_dir<P, K>(
+    shared: &Shared<P, K>,
+    dir: &Pending,
+    tx: &Sender<Sent>,
+) -> Result<(), mpsc::SendError<Sent>>
+where
+    P: Fn(&DirEntry) -> bool,
+    K: Fn(&DirEntry) -> bool,
+{
+    let follow_all = shared.follow == Follow::Always;
+    let walker = WalkDir::new(&dir.path)
+        .min_depth(1)
+        .max_depth(1)
+        .follow_links(follow_all);
+    for result in walker {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => match dangling_link(&err) {
+                Some(entry) => entry,
+                None => {
+                    tx.send(Err(err.into()))?;
+                    continue;
+                }
+            },
+        };
+        if (shared.prune)(&entry) {
+            continue;
+        }
+
+        if entry.file_type().is_dir() {
+            let mut ancestors = None;
+            if follow_all {
+                if let Ok(id) = dir_id(entry.path()) {
+                    if let Some(ancestor) = loop_ancestor(dir.ancestors.as_ref(), &id) {
+                        tx.send(Err(EntryError::fs_loop(entry.path(), ancestor)))?;
+                        continue;
+                    }
+                    ancestors = Some(Arc::new(Ancestor {
+                        id,
+                        path: entry.path().to_path_buf(),
+                        parent: dir.ancestors.clone(),
+                    }));
+                }
+            }
+
+            shared.queue.lock().unwrap().dirs.push(Pending {
+                path: entry.path().to_path_buf(),
+                depth: dir.depth + 1,
+                ancestors,
+            });
+            shared.ready.notify_one();
+        }
+
+        if (shared.keep)(&entry) {
+            tx.send(Ok(Found { entry, depth: dir.depth + 1 }))?;
+        }
+    }
+    Ok(())
+}
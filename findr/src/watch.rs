@@ -0,0 +1,191 @@
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+use notify::{event::ModifyKind, EventKind, RecursiveMode, Watcher};
+use walkdir::{DirEntry, WalkDir};
+use crate::{
+    errors::{EntryError, Errors},
+    RetType,
+};
+
+// Editors and compilers touch a file several times in a row, so events are
+// gathered until things have been quiet for this long.
+const SETTLE: Duration = Duration::from_millis(50);
+
+/// Something created or changed below one of the start paths.
+pub struct Change {
+    /// The index of the start path.
+    pub root: usize,
+    /// The path below the start path.
+    pub below: PathBuf,
+    /// Whether it is new, rather than changed.
+    pub created: bool,
+}
+
+/// Start paths being watched, with the changes made to them queued up
+/// until `run` hands them out.
+pub struct Watch {
+    // Dropping the watcher would stop the events.
+    _watcher: notify::RecommendedWatcher,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    // The index and absolute path of each start path that is watched.
+    bases: Vec<(usize, PathBuf)>,
+}
+
+impl Watch {
+    /// Starts watching `roots`, which is done before they are walked so that
+    /// nothing made meanwhile is missed. Start paths that can't be watched
+    /// are reported and left out.
+    pub fn start(roots: &[String], errors: &Errors) -> RetType<Watch> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+
+        // Events name absolute paths, which are mapped back to how the start
+        // paths were given.
+        let mut bases = vec![];
+        for (i, root) in roots.iter().enumerate() {
+            let base = match fs::canonicalize(root) {
+                Ok(base) => base,
+                Err(e) => {
+                    errors.report(EntryError::new(Path::new(root), e));
+                    continue;
+                }
+            };
+            match watcher.watch(&base, RecursiveMode::Recursive) {
+                Ok(()) => bases.push((i, base)),
+                Err(e) => errors.report(entry_error(Path::new(root), e)),
+            }
+        }
+
+        // The longest base wins when start paths are nested.
+        bases.sort_by_key(|(_, base)| Reverse(base.components().count()));
+        Ok(Watch { _watcher: watcher, rx, bases })
+    }
+
+    /// Hands each batch of changes to `changed`, in the order they happened
+    /// and with each path only once, until it returns false. Changes made
+    /// since `start` come first.
+    pub fn run<F>(self, errors: &Errors, mut changed: F) -> RetType<()>
+    where
+        F: FnMut(Vec<Change>) -> RetType<bool>,
+    {
+        if self.bases.is_empty() {
+            return Ok(());
+        }
+
+        let locate = |path: &Path| {
+            self.bases.iter().find_map(|(i, base)| {
+                path.strip_prefix(base).ok().map(|below| (*i, below.to_path_buf()))
+            })
+        };
+
+        while let Ok(first) = self.rx.recv() {
+            let mut batch: Vec<Change> = vec![];
+            let mut next = Some(first);
+            while let Some(result) = next {
+                match result {
+                    Ok(event) => {
+                        // Renames count as new, while reads and removals are left out.
+                        let created = match event.kind {
+                            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => Some(true),
+                            EventKind::Modify(_) => Some(false),
+                            _ => None,
+                        };
+                        for path in event.paths {
+                            let (created, (root, below)) = match (created, locate(&path)) {
+                                (Some(created), Some(found)) => (created, found),
+                                _ => continue,
+                            };
+                            let seen = batch
+                                .iter_mut()
+                                .find(|change| change.root == root && change.below == below);
+                            match seen {
+                                Some(change) => change.created |= created,
+                                None => batch.push(Change { root, below, created }),
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let path = e.paths.first().cloned().unwrap_or_else(|| self.bases[0].1.clone());
+                        errors.report(entry_error(&path, e));
+                    }
+                }
+                next = self.rx.recv_timeout(SETTLE).ok();
+            }
+
+            if !batch.is_empty() && !changed(batch)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Walks what a batch of changes below `roots` made: each changed path, and
+/// everything in those that are new. As in the walk of the start paths,
+/// nothing pruned or inside something pruned is walked, and only the entries
+/// kept are handed to `emit`, with their depth below the start path. `prune`
+/// is also given the index of the start path. Returns false once `emit` does.
+pub fn walk_changes<P, K, E>(
+    roots: &[String],
+    changes: Vec<Change>,
+    follow_links: bool,
+    prune: &P,
+    keep: &K,
+    errors: &Errors,
+    mut emit: E,
+) -> RetType<bool>
+where
+    P: Fn(usize, &DirEntry) -> bool,
+    K: Fn(&DirEntry) -> bool,
+    E: FnMut(&DirEntry, usize) -> RetType<bool>,
+{
+    // A new directory and what was made in it come in one batch.
+    let mut emitted = HashSet::new();
+    for change in changes {
+        let prune = |entry: &DirEntry| prune(change.root, entry);
+        let mut path = PathBuf::from(&roots[change.root]);
+        let pruned = change.below.iter().any(|name| {
+            path.push(name);
+            let mut walker = WalkDir::new(&path).max_depth(0).into_iter();
+            walker.next().is_some_and(|result| result.is_ok_and(|entry| prune(&entry)))
+        });
+        if pruned {
+            continue;
+        }
+
+        // A new directory may already hold entries, for instance when it was
+        // moved here.
+        let depth = change.below.iter().count();
+        let walker = WalkDir::new(&path)
+            .max_depth(if change.created { usize::MAX } else { 0 })
+            .follow_links(follow_links)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !prune(entry));
+        for result in walker {
+            match result {
+                Ok(entry) if keep(&entry) && emitted.insert(entry.path().to_path_buf()) => {
+                    if !emit(&entry, depth + entry.depth())? {
+                        return Ok(false);
+                    }
+                }
+                Ok(_) => {}
+                // It was changed, then removed again.
+                Err(err)
+                    if err.io_error().is_some_and(|e| e.kind() == io::ErrorKind::NotFound) => {}
+                Err(err) => errors.report(err.into()),
+            }
+        }
+    }
+    Ok(true)
+}
+
+// notify's own message lists the paths, which EntryError already gives.
+fn entry_error(path: &Path, e: notify::Error) -> EntryError {
+    EntryError::new(path, notify::Error::new(e.kind))
+}
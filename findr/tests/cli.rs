@@ -1,7 +1,11 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -28,46 +32,162 @@ fn skips_bad_dir() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error [23][)]", &bad);
     Command::cargo_bin(PRG)?
-        .arg(&bad)
+        .args([&bad, "tests/inputs/g.csv"])
         .assert()
-        .success()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn quiet_errors() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args([&bad, "tests/inputs/g.csv", "--quiet-errors"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr("");
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_bad_name() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["--name", "*.csv"])
+        .args(["--name", "*.csv"])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid --name \"*.csv\""));
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_contains() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--contains", "(TODO"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --contains \"(TODO\""));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_bad_type() -> TestResult {
     let expected = "error: \"x\" isn't a valid value for '--type <TYPE>...'";
     Command::cargo_bin(PRG)?
-        .args(&["--type", "x"])
+        .args(["--type", "x"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_jobs() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--jobs", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --jobs \"0\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_limit() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--limit", "none"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --limit \"none\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_perm() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--perm", "-u+q"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --perm \"-u+q\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_user() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--user", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Invalid --user \"{}\"",
+            bad
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_times() -> TestResult {
+    for (flag, val) in [
+        ("--newer-than", "3x"),
+        ("--older-than", "2024-13-01"),
+        ("--older-than", "h"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args([flag, val])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Invalid {} \"{}\"",
+                flag, val
+            )));
+    }
+
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--changed-since-file", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Invalid --changed-since-file \"{}\"",
+            bad
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_pick_without_terminal() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--pick"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("--pick needs a terminal\n");
+    Ok(())
+}
+
 // --------------------------------------------------
 #[cfg(windows)]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Owned(format!("{}.windows", expected_file))
     format!("{}.windows", expected_file).into()
 }
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -92,6 +212,58 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_ordered(args: &[&str], expected_file: &str) -> TestResult {
+    let file = format_file_name(expected_file);
+    let expected = fs::read_to_string(file.as_ref())?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+fn scratch_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = env::temp_dir().join(format!("findr-{}", gen_bad_file()));
+    fs::create_dir(&dir)?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+// Runs findr on `dir` and returns the names of what it printed, in order.
+fn found_in_order(
+    dir: &Path,
+    args: &[&str],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cmd = Command::cargo_bin(PRG)?
+        .arg(dir)
+        .args(args)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    Ok(stdout
+        .lines()
+        .map(|line| {
+            Path::new(line).file_name().map_or(String::new(), |name| {
+                name.to_string_lossy().into_owned()
+            })
+        })
+        .collect())
+}
+
+// --------------------------------------------------
+// Like `found_in_order`, but sorted.
+fn found_names(
+    dir: &Path,
+    args: &[&str],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut names = found_in_order(dir, args)?;
+    names.sort();
+    Ok(names)
+}
+
 // --------------------------------------------------
 #[test]
 fn path1() -> TestResult {
@@ -281,6 +453,740 @@ fn path_g() -> TestResult {
     run(&["tests/inputs/g.csv"], "tests/expected/path_g.txt")
 }
 
+// --------------------------------------------------
+#[test]
+fn file_roots_filtered() -> TestResult {
+    let roots = ["tests/inputs/g.csv", "tests/inputs/a/a.txt"];
+    for jobs in ["1", "2"] {
+        Command::cargo_bin(PRG)?
+            .args(roots)
+            .args(["-j", jobs, "-n", "csv$"])
+            .assert()
+            .success()
+            .stdout("tests/inputs/g.csv\n");
+        Command::cargo_bin(PRG)?
+            .args(roots)
+            .args(["-j", jobs, "-t", "d"])
+            .assert()
+            .success()
+            .stdout("");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn link_root_filtered() -> TestResult {
+    for jobs in ["1", "2"] {
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs/d/b.csv", "-j", jobs, "-t", "l", "-n", "b"])
+            .assert()
+            .success()
+            .stdout("tests/inputs/d/b.csv\n");
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs/d/b.csv", "-j", jobs, "-t", "f"])
+            .assert()
+            .success()
+            .stdout("");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn jobs_path1() -> TestResult {
+    run(&["tests/inputs", "-j", "4"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn jobs_type_f_name_a() -> TestResult {
+    run(
+        &["tests/inputs", "-j", "4", "-t", "f", "-n", "a"],
+        "tests/expected/type_f_name_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_path1() -> TestResult {
+    run_ordered(&["tests/inputs", "--sort"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_before_path() -> TestResult {
+    run_ordered(&["--sort", "tests/inputs"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_jobs_path1() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--sort", "-j", "4"],
+        "tests/expected/path1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn perm() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = scratch_dir()?;
+    for (name, mode) in
+        [("plain", 0o644), ("setuid", 0o4755), ("shared", 0o666)]
+    {
+        let path = dir.join(name);
+        fs::write(&path, "")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+    }
+
+    assert_eq!(found_names(&dir, &["-t", "f", "--perm", "644"])?, ["plain"]);
+    assert_eq!(found_names(&dir, &["--perm", "-4000"])?, ["setuid"]);
+    assert_eq!(
+        found_names(&dir, &["-t", "f", "--perm", "-u+x"])?,
+        ["setuid"]
+    );
+    assert_eq!(found_names(&dir, &["--perm", "/o+w"])?, ["shared"]);
+    assert_eq!(
+        found_names(&dir, &["-t", "f", "--perm", "/u+s,o+w"])?,
+        ["setuid", "shared"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn user_group() -> TestResult {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = scratch_dir()?;
+    fs::write(dir.join("mine"), "")?;
+    let meta = fs::metadata(&dir)?;
+    let uid = meta.uid().to_string();
+    let gid = meta.gid().to_string();
+
+    assert_eq!(found_names(&dir, &["-t", "f", "--user", &uid])?, ["mine"]);
+    assert_eq!(found_names(&dir, &["-t", "f", "--group", &gid])?, ["mine"]);
+    assert!(
+        found_names(&dir, &["--user", &(meta.uid() + 1).to_string()])?
+            .is_empty()
+    );
+    assert!(found_names(&dir, &["--nouser"])?.is_empty());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn type_p_s() -> TestResult {
+    use std::os::unix::net::UnixListener;
+
+    let dir = scratch_dir()?;
+    let fifo = dir.join("fifo");
+    std::process::Command::new("mkfifo").arg(&fifo).status()?;
+    let _listener = UnixListener::bind(dir.join("socket"))?;
+    fs::write(dir.join("file"), "")?;
+
+    assert_eq!(found_names(&dir, &["-t", "p"])?, ["fifo"]);
+    assert_eq!(found_names(&dir, &["-t", "s"])?, ["socket"]);
+    assert_eq!(found_names(&dir, &["-t", "p", "s"])?, ["fifo", "socket"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+fn gen_repo() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = scratch_dir()?;
+    for sub in [".git", "src", "target"] {
+        fs::create_dir(dir.join(sub))?;
+    }
+    fs::write(dir.join(".gitignore"), "target/\n*.log\n!keep.log\n")?;
+    fs::write(dir.join(".ignore"), "secret\n")?;
+    fs::write(dir.join("src/.gitignore"), "*.bak\n")?;
+    for file in [
+        ".git/config",
+        ".hidden",
+        "a.log",
+        "keep.log",
+        "secret",
+        "src/main.rs",
+        "src/main.rs.bak",
+        "target/out",
+    ] {
+        fs::write(dir.join(file), "")?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore() -> TestResult {
+    let dir = gen_repo()?;
+    let expected = [
+        ".gitignore",
+        ".gitignore",
+        ".hidden",
+        ".ignore",
+        "keep.log",
+        "main.rs",
+    ];
+
+    assert_eq!(found_names(&dir, &["-t", "f", "--gitignore"])?, expected);
+    assert_eq!(
+        found_names(&dir, &["-t", "f", "--gitignore", "-j", "4"])?,
+        expected
+    );
+    assert_eq!(
+        found_names(&dir, &["-t", "d", "--gitignore"])?,
+        [dir.file_name().unwrap().to_string_lossy().as_ref(), "src"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_hidden() -> TestResult {
+    let dir = gen_repo()?;
+
+    assert_eq!(
+        found_names(&dir, &["-t", "f", "--no-hidden"])?,
+        [
+            "a.log",
+            "keep.log",
+            "main.rs",
+            "main.rs.bak",
+            "out",
+            "secret"
+        ]
+    );
+    assert_eq!(
+        found_names(&dir, &["-t", "f", "--gitignore", "--no-hidden"])?,
+        ["keep.log", "main.rs"]
+    );
+    assert_eq!(
+        found_names(&dir.join(".git"), &["-t", "f", "--no-hidden"])?,
+        ["config"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn empty_broken() -> TestResult {
+    use std::os::unix::fs::symlink;
+
+    let dir = scratch_dir()?;
+    fs::create_dir(dir.join("hollow"))?;
+    fs::create_dir(dir.join("full"))?;
+    fs::write(dir.join("full/data"), "data")?;
+    fs::write(dir.join("zero"), "")?;
+    symlink("full/data", dir.join("good"))?;
+    symlink("missing", dir.join("dangling"))?;
+
+    assert_eq!(found_names(&dir, &["--empty"])?, ["hollow", "zero"]);
+    assert_eq!(found_names(&dir, &["--empty", "-t", "d"])?, ["hollow"]);
+    assert_eq!(found_names(&dir, &["--broken"])?, ["dangling"]);
+    assert_eq!(found_names(&dir, &["--xtype", "l"])?, ["dangling"]);
+    assert_eq!(
+        found_names(&dir, &["--xtype", "f"])?,
+        ["data", "good", "zero"]
+    );
+    assert_eq!(found_names(&dir, &["-t", "l", "--xtype", "f"])?, ["good"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_links() -> TestResult {
+    use std::os::unix::fs::symlink;
+
+    let dir = scratch_dir()?;
+    fs::create_dir_all(dir.join("real/sub"))?;
+    fs::write(dir.join("real/sub/f"), "")?;
+    symlink("..", dir.join("real/sub/up"))?;
+    symlink("nowhere", dir.join("real/dangle"))?;
+    symlink("real", dir.join("link"))?;
+
+    let root = dir.join("link");
+    assert_eq!(found_names(&root, &[])?, ["link"]);
+    assert_eq!(found_names(&root, &["-t", "l"])?, ["link"]);
+
+    let below = ["dangle", "f", "link", "sub", "up"];
+    for jobs in ["1", "3"] {
+        assert_eq!(found_names(&root, &["-H", "-j", jobs])?, below);
+        assert_eq!(
+            found_names(&root, &["-H", "-j", jobs, "-t", "d"])?,
+            ["link", "sub"]
+        );
+        assert_eq!(
+            found_names(&root, &["-H", "-j", jobs, "-t", "l"])?,
+            ["dangle", "up"]
+        );
+
+        // The loop is reported, not walked, and the dangling link is kept.
+        let cmd = Command::cargo_bin(PRG)?
+            .arg(&dir)
+            .args(["-L", "-j", jobs, "-t", "l"])
+            .assert()
+            .failure();
+        let out = cmd.get_output();
+        let mut names: Vec<_> = String::from_utf8(out.stdout.clone())?
+            .lines()
+            .map(|line| line.replacen(dir.to_str().unwrap(), "", 1))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["/link/dangle", "/real/dangle"]);
+        let stderr = String::from_utf8(out.stderr.clone())?;
+        assert_eq!(stderr.matches("File system loop detected").count(), 2);
+        let link = dir.join("link");
+        assert!(stderr.contains(&format!(
+            "{}: File system loop detected, it is the same directory as {}",
+            link.join("sub/up").display(),
+            link.display()
+        )));
+    }
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates() -> TestResult {
+    let dir = scratch_dir()?;
+    fs::create_dir(dir.join("sub"))?;
+    let big = "x".repeat(5000);
+    for (name, contents) in [
+        ("a.jpg", "same"),
+        ("sub/b.jpg", "same"),
+        ("c.txt", "same"),
+        ("d.jpg", "diff"),
+        ("big1.jpg", big.as_str()),
+        ("big2.jpg", big.as_str()),
+        ("big3.jpg", &format!("{}y", &big[1..])),
+        ("empty1.jpg", ""),
+        ("empty2.jpg", ""),
+    ] {
+        fs::write(dir.join(name), contents)?;
+    }
+
+    let expected = format!(
+        "{}\n{}\n\n{}\n{}\n",
+        dir.join("a.jpg").display(),
+        dir.join("sub/b.jpg").display(),
+        dir.join("big1.jpg").display(),
+        dir.join("big2.jpg").display(),
+    );
+    Command::cargo_bin(PRG)?
+        .arg(&dir)
+        .args(["--duplicates", "-t", "f", "-n", "[.]jpg$"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn json() -> TestResult {
+    use std::os::unix::fs::symlink;
+
+    let dir = scratch_dir()?;
+    fs::create_dir(dir.join("sub"))?;
+    fs::write(dir.join("sub/data"), "hello")?;
+    symlink("sub/data", dir.join("link"))?;
+
+    let cmd = Command::cargo_bin(PRG)?
+        .arg(&dir)
+        .args(["--json", "--sort", "-j", "2"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let objects = stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+    assert_eq!(objects.len(), 4);
+    let (link, data) = (&objects[1], &objects[3]);
+    assert_eq!(link["path"], dir.join("link").to_string_lossy().as_ref());
+    assert_eq!(link["type"], "l");
+    assert_eq!(link["target"], "sub/data");
+    assert_eq!(link["depth"], 1);
+    assert_eq!(data["type"], "f");
+    assert_eq!(data["size"], 5);
+    assert_eq!(data["target"], serde_json::Value::Null);
+    assert_eq!(data["depth"], 2);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn ls() -> TestResult {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = scratch_dir()?;
+    fs::write(dir.join("tool"), "#!/bin/sh\n")?;
+    fs::set_permissions(dir.join("tool"), fs::Permissions::from_mode(0o4755))?;
+    symlink("tool", dir.join("link"))?;
+
+    let cmd = Command::cargo_bin(PRG)?
+        .arg(&dir)
+        .args(["--ls", "--sort", "-t", "f", "l"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+
+    // inode, blocks, mode, links, user, group, size, month, day, time, path
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0][2], "lrwxrwxrwx");
+    assert_eq!(lines[0][6], "4");
+    assert_eq!(
+        lines[0][10..],
+        [dir.join("link").to_str().unwrap(), "->", "tool"]
+    );
+    assert_eq!(lines[1][2], "-rwsr-xr-x");
+    assert_eq!(lines[1][6], "10");
+    assert_eq!(lines[1][10..], [dir.join("tool").to_str().unwrap()]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains() -> TestResult {
+    let dir = scratch_dir()?;
+    fs::create_dir(dir.join("TODO"))?;
+    fs::write(dir.join("a.txt"), "hello\nTODO: fix\nmore\n")?;
+    fs::write(dir.join("b.txt"), "nothing to do\r\n")?;
+    fs::write(dir.join("c.bin"), b"\xff\xfe\x00TODO\x00")?;
+
+    assert_eq!(
+        found_names(&dir, &["--contains", "TODO"])?,
+        ["a.txt", "c.bin"]
+    );
+    assert_eq!(
+        found_names(&dir, &["--contains", "^TODO:", "-n", "txt$"])?,
+        ["a.txt"]
+    );
+    assert_eq!(
+        found_names(&dir, &["--contains", "fix$|to do$"])?,
+        ["a.txt", "b.txt"]
+    );
+    assert_eq!(
+        found_names(&dir, &["--contains", "(?i)nothing|fix", "-j", "3"])?,
+        ["a.txt", "b.txt"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_without_newlines() -> TestResult {
+    let dir = scratch_dir()?;
+    let blob = |at: usize, text: &str| {
+        let mut bytes = vec![0; 1 << 20];
+        bytes.splice(at..at + text.len(), text.bytes());
+        bytes
+    };
+    // Around where findr cuts a long line into pieces.
+    fs::write(dir.join("a.bin"), blob(65533, "NEEDLE"))?;
+    fs::write(dir.join("b.bin"), blob((1 << 20) - 4, "tail"))?;
+    fs::write(dir.join("c.bin"), blob(65532, "tail"))?;
+    fs::write(dir.join("d.bin"), blob(61440, "START"))?;
+
+    assert_eq!(found_names(&dir, &["--contains", "NEEDLE"])?, ["a.bin"]);
+    assert_eq!(found_names(&dir, &["--contains", "tail$"])?, ["b.bin"]);
+    assert_eq!(
+        found_names(&dir, &["--contains", "tail"])?,
+        ["b.bin", "c.bin"]
+    );
+    assert!(found_names(&dir, &["--contains", "^START"])?.is_empty());
+    assert_eq!(found_names(&dir, &["--contains", "START"])?, ["d.bin"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_reverse() -> TestResult {
+    let expected = [
+        "inputs", "g.csv", "f", "f.txt", "d", "e", "e.mp3", "d.txt", "d.tsv",
+        "b.csv", "a", "b", "c", "c.mp3", "b.csv", "a.txt",
+    ];
+    let dir = Path::new("tests/inputs");
+
+    assert_eq!(found_in_order(dir, &["--sort", "--reverse"])?, expected);
+    assert_eq!(
+        found_in_order(dir, &["--sort=name", "--reverse", "-j", "4"])?,
+        expected
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_size_mtime_limit() -> TestResult {
+    let dir = scratch_dir()?;
+    let now = std::time::SystemTime::now();
+    for (name, contents, age) in
+        [("small", "1", 30), ("big", "333", 20), ("mid", "22", 10)]
+    {
+        fs::write(dir.join(name), contents)?;
+        fs::File::options()
+            .write(true)
+            .open(dir.join(name))?
+            .set_modified(now - std::time::Duration::from_secs(age))?;
+    }
+
+    let found = |args: &[&str]| found_in_order(&dir, args);
+    assert_eq!(found(&["-t", "f", "--sort=size"])?, ["big", "mid", "small"]);
+    assert_eq!(
+        found(&["-t", "f", "--sort=size", "--reverse"])?,
+        ["small", "mid", "big"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--sort=mtime", "-j", "2"])?,
+        ["mid", "big", "small"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--sort=size", "--limit", "2"])?,
+        ["big", "mid"]
+    );
+    assert_eq!(found(&["-t", "f", "--limit", "1"])?.len(), 1);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn newer_older() -> TestResult {
+    use std::time::{Duration, SystemTime};
+
+    let dir = scratch_dir()?;
+    let now = SystemTime::now();
+    let day = Duration::from_secs(24 * 60 * 60);
+    for (name, time) in [
+        ("stamp", now - 2 * day),
+        ("fresh", now - day / 24),
+        ("stale", SystemTime::UNIX_EPOCH + 365 * day),
+    ] {
+        fs::write(dir.join(name), "")?;
+        fs::File::options()
+            .write(true)
+            .open(dir.join(name))?
+            .set_modified(time)?;
+    }
+
+    let found = |args: &[&str]| found_names(&dir, args);
+    assert_eq!(found(&["-t", "f", "--newer-than", "1d"])?, ["fresh"]);
+    assert_eq!(
+        found(&["-t", "f", "--older-than", "1d"])?,
+        ["stale", "stamp"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--newer-than", "3d", "--older-than", "30m"])?,
+        ["fresh", "stamp"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--older-than", "1971-06-01"])?,
+        ["stale"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--older-than", "1971-06-01T00:00:00Z"])?,
+        ["stale"]
+    );
+    assert_eq!(
+        found(&["-t", "f", "--newer-than", "1971-06-01 12:00"])?,
+        ["fresh", "stamp"]
+    );
+
+    let stamp = dir.join("stamp");
+    let stamp = stamp.to_str().unwrap();
+    assert_eq!(
+        found(&["-t", "f", "--changed-since-file", stamp])?,
+        ["fresh"]
+    );
+    // The later of the two limits applies.
+    assert_eq!(
+        found(&[
+            "-t",
+            "f",
+            "--changed-since-file",
+            stamp,
+            "--newer-than",
+            "1w"
+        ])?,
+        ["fresh"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn watch() -> TestResult {
+    use assert_cmd::cargo::CommandCargoExt;
+    use std::{
+        io::{BufRead, BufReader},
+        process::{self, Stdio},
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    let dir = scratch_dir()?;
+    fs::write(dir.join("old.proto"), "")?;
+    let mut child = process::Command::cargo_bin(PRG)?
+        .arg(&dir)
+        .args(["--watch", "-t", "f", "-n", "proto$"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(10)).ok();
+
+    // The watch starts before the walk, so once the walk has printed
+    // something, changes are seen.
+    let mut lines = vec![next()];
+    fs::write(dir.join("notes.txt"), "")?;
+    fs::create_dir_all(dir.join("sub/deep"))?;
+    fs::write(dir.join("sub/deep/new.proto"), "")?;
+    lines.push(next());
+    fs::write(dir.join("old.proto"), "changed")?;
+    lines.push(next());
+    child.kill()?;
+    child.wait()?;
+
+    let found = |names: &[&str]| {
+        let path: PathBuf =
+            dir.iter().chain(names.iter().map(|n| n.as_ref())).collect();
+        Some(path.display().to_string())
+    };
+    assert_eq!(
+        lines,
+        [
+            found(&["old.proto"]),
+            found(&["sub", "deep", "new.proto"]),
+            found(&["old.proto"])
+        ]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tree() -> TestResult {
+    run_ordered(&["tests/inputs", "--tree"], "tests/expected/tree.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_type_f_name_csv() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--tree", "-t", "f", "-n", "csv$"],
+        "tests/expected/tree_type_f_name_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_du() -> TestResult {
+    let dir = scratch_dir()?;
+    fs::create_dir_all(dir.join("build/deps"))?;
+    fs::create_dir(dir.join("src"))?;
+    fs::write(dir.join("build/deps/big.o"), "x".repeat(3000))?;
+    fs::write(dir.join("build/small.o"), "x".repeat(100))?;
+    fs::write(dir.join("src/main.rs"), "x".repeat(500))?;
+
+    let root = dir.to_str().unwrap();
+    let expected = format!(
+        "\
+[3.5K]  {} (3 files)
+├── [3.0K]  build (2 files)
+│   ├── [2.9K]  deps (1 file)
+│   │   └── [2.9K]  big.o
+│   └── [ 100]  small.o
+└── [ 500]  src (1 file)
+    └── [ 500]  main.rs
+
+3 directories, 3 files
+",
+        root
+    );
+    Command::cargo_bin(PRG)?
+        .args([root, "--tree", "--du", "--sort=size"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    // Directories that only hold matches are shown too.
+    let expected = format!(
+        "\
+{}
+├── src
+│   └── main.rs
+└── build
+    └── small.o
+
+2 directories, 2 files
+",
+        root
+    );
+    Command::cargo_bin(PRG)?
+        .args([root, "--tree", "-t", "f", "-n", "^[ms]"])
+        .args(["--sort", "--reverse", "-j", "2"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 #[cfg(not(windows))]
@@ -295,14 +1201,14 @@ fn unreadable_dir() -> TestResult {
     //permissions.set_mode(0o000);
 
     std::process::Command::new("chmod")
-        .args(&["000", dirname])
+        .args(["000", dirname])
         .status()
         .expect("failed");
 
     let cmd = Command::cargo_bin(PRG)?
         .arg("tests/inputs")
         .assert()
-        .success();
+        .failure();
     fs::remove_dir(dirname)?;
 
     let out = cmd.get_output();
@@ -314,6 +1220,8 @@ fn unreadable_dir() -> TestResult {
     assert_eq!(lines.len(), 17);
 
     let stderr = String::from_utf8(out.stderr.clone())?;
-    assert!(stderr.contains("cant-touch-this: Permission denied (os error 13)"));
+    assert!(stderr.starts_with(
+        "tests/inputs/cant-touch-this: Permission denied (os error 13)"
+    ));
     Ok(())
 }